@@ -0,0 +1,659 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek},
+    mem,
+    ops::{ControlFlow, Range},
+};
+
+use flate2::{Decompress, FlushDecompress, Status};
+use tracing::{debug, trace, warn};
+use zip::{CompressionMethod, ZipArchive, read::ZipFile};
+
+use crate::{
+    ExtractedJarError, MAGIC_ZIP_HEADER,
+    progress::Progress,
+    sevenz::{MAGIC_7Z_HEADER, SevenZArchive},
+    tar,
+};
+
+/// Callback handed to [`ArchiveReader::for_each_entry`], getting the name of an entry and a reader for its content.
+/// Returning [`ControlFlow::Break`] stops looking at the rest of the archive
+pub type EntryVisitor<'a> =
+    dyn FnMut(&str, &mut dyn Read) -> Result<ControlFlow<()>, ExtractedJarError> + 'a;
+
+/// Something that contains files, like a jar, a tar or a 7z archive, so that the scanning doesn't care which one it is.
+///
+/// Readers only need to be able to walk their entries once, in whatever order they like,
+/// which is all that streaming formats like a gzipped tar can offer.
+pub trait ArchiveReader {
+    /// Calls `visit` for every regular file in the archive. Entries that `visit` doesn't read from should not be decompressed.
+    fn for_each_entry(&mut self, visit: &mut EntryVisitor) -> Result<(), ExtractedJarError>;
+
+    /// Whether jars inside of this archive should be scanned as well
+    fn scan_nested_jars(&self) -> bool {
+        false
+    }
+
+    /// Names of all regular files in the archive, without reading any of their content
+    fn entry_names(&mut self) -> Result<Vec<String>, ExtractedJarError> {
+        let mut names = Vec::new();
+        self.for_each_entry(&mut |name, _| {
+            names.push(name.to_owned());
+            Ok(ControlFlow::Continue(()))
+        })?;
+        Ok(names)
+    }
+}
+
+impl<R: Read + Seek> ArchiveReader for ZipArchive<R> {
+    fn for_each_entry(&mut self, visit: &mut EntryVisitor) -> Result<(), ExtractedJarError> {
+        trace!("Got archive with {} entries", self.len());
+        zip_entries(self, 0..self.len(), visit)
+    }
+
+    /// Zips have all names in the central directory, no need to look at the entries at all
+    fn entry_names(&mut self) -> Result<Vec<String>, ExtractedJarError> {
+        Ok(self
+            .file_names()
+            .filter(|name| !name.ends_with('/'))
+            .map(str::to_owned)
+            .collect())
+    }
+}
+
+/// An archive that carries the jars it depends on, like the `WEB-INF/lib` of a war, which count just like its own classes
+pub struct Bundle<'a>(pub &'a mut dyn ArchiveReader);
+
+impl ArchiveReader for Bundle<'_> {
+    fn for_each_entry(&mut self, visit: &mut EntryVisitor) -> Result<(), ExtractedJarError> {
+        self.0.for_each_entry(visit)
+    }
+
+    fn scan_nested_jars(&self) -> bool {
+        true
+    }
+
+    fn entry_names(&mut self) -> Result<Vec<String>, ExtractedJarError> {
+        self.0.entry_names()
+    }
+}
+
+/// Part of the entries of a zip, so that several threads can go through one zip at the same time with a handle each
+pub struct ZipSlice<R> {
+    archive: ZipArchive<R>,
+    range: Range<usize>,
+}
+
+impl<R: Read + Seek> ZipSlice<R> {
+    /// How many entries (including directories) are in the slice
+    pub fn entries(&self) -> usize {
+        self.range.len()
+    }
+}
+
+impl<R: Read + Seek> ArchiveReader for ZipSlice<R> {
+    fn for_each_entry(&mut self, visit: &mut EntryVisitor) -> Result<(), ExtractedJarError> {
+        trace!("Got entries {:?} of an archive", self.range);
+        zip_entries(&mut self.archive, self.range.clone(), visit)
+    }
+}
+
+/// Keeps archives within the given limits while reading them, so that one that is crafted to blow up (like a zip bomb)
+/// fails with [`ExtractedJarError::TooLarge`] instead of eating all memory
+pub struct Capped<'a> {
+    pub archive: &'a mut dyn ArchiveReader,
+    pub max_entries: usize,
+    /// per entry, after decompressing it
+    pub max_entry_bytes: u64,
+}
+
+impl ArchiveReader for Capped<'_> {
+    fn for_each_entry(&mut self, visit: &mut EntryVisitor) -> Result<(), ExtractedJarError> {
+        let (max_entries, max_entry_bytes) = (self.max_entries, self.max_entry_bytes);
+        let mut entries = 0;
+        self.archive.for_each_entry(&mut |name, entry| {
+            entries += 1;
+            check_entries(entries, max_entries)?;
+            let mut entry = LimitedEntry {
+                entry,
+                left: max_entry_bytes,
+                exceeded: false,
+            };
+            let result = visit(name, &mut entry);
+            // whatever the error looks like by now, this is what it's actually about
+            if entry.exceeded {
+                return Err(ExtractedJarError::TooLarge {
+                    entry: name.to_owned(),
+                    limit: format!("{max_entry_bytes} bytes"),
+                });
+            }
+            result
+        })
+    }
+
+    fn scan_nested_jars(&self) -> bool {
+        self.archive.scan_nested_jars()
+    }
+}
+
+/// Tells `progress` about every entry that goes by, without changing anything about them
+pub struct Counted<'a> {
+    pub archive: &'a mut dyn ArchiveReader,
+    pub progress: &'a Progress,
+}
+
+impl ArchiveReader for Counted<'_> {
+    fn for_each_entry(&mut self, visit: &mut EntryVisitor) -> Result<(), ExtractedJarError> {
+        let progress = self.progress;
+        self.archive.for_each_entry(&mut |name, entry| {
+            progress.tick();
+            visit(name, entry)
+        })
+    }
+
+    fn scan_nested_jars(&self) -> bool {
+        self.archive.scan_nested_jars()
+    }
+}
+
+/// Fails for archives with more than `max` entries
+pub fn check_entries(entries: usize, max: usize) -> Result<(), ExtractedJarError> {
+    if entries > max {
+        return Err(ExtractedJarError::TooLarge {
+            entry: String::new(),
+            limit: format!("{max} entries"),
+        });
+    }
+    Ok(())
+}
+
+/// Fails once more than `left` bytes are read
+struct LimitedEntry<'a> {
+    entry: &'a mut dyn Read,
+    left: u64,
+    exceeded: bool,
+}
+
+impl Read for LimitedEntry<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // one byte more than allowed, to tell an entry of exactly the limit apart from a larger one
+        let max = self.left.saturating_add(1).min(buf.len() as u64) as usize;
+        let read = self.entry.read(&mut buf[..max])?;
+        if read as u64 > self.left {
+            self.exceeded = true;
+            return Err(io::Error::other("entry is larger than the limit"));
+        }
+        self.left -= read as u64;
+        Ok(read)
+    }
+}
+
+/// Zips with fewer entries than this aren't worth the threads
+pub const MIN_ENTRIES_TO_SPLIT: usize = 1024;
+
+/// Splits a zip into (at most) `count` slices of about the same size. `open` is called once per slice, every slice
+/// gets a handle of its own. If the zip is too small for that to be worth it, there is a single slice with everything
+pub fn zip_slices<R: Read + Seek>(
+    open: impl Fn() -> io::Result<R>,
+    count: usize,
+    min_entries: usize,
+) -> Result<Vec<ZipSlice<R>>, ExtractedJarError> {
+    let first = ZipArchive::new(open()?)?;
+    let len = first.len();
+    if count < 2 || len < min_entries {
+        // reading the central directory again would take about as long as reading the headers of the classes
+        return Ok(vec![ZipSlice {
+            archive: first,
+            range: 0..len,
+        }]);
+    }
+    let size = len.div_ceil(count);
+    debug!("Splitting archive with {len} entries into slices of {size}");
+    let mut slices = vec![ZipSlice {
+        archive: first,
+        range: 0..size,
+    }];
+    for start in (size..len).step_by(size) {
+        slices.push(ZipSlice {
+            archive: ZipArchive::new(open()?)?,
+            range: start..(start + size).min(len),
+        });
+    }
+    Ok(slices)
+}
+
+/// Whether `file` starts like a zip does. Anything that can't be read isn't one
+pub fn is_zip(file: &str) -> bool {
+    let mut buffer = [0; 4];
+    File::open(file)
+        .and_then(|mut file| file.read_exact(&mut buffer))
+        .is_ok_and(|_| buffer == MAGIC_ZIP_HEADER)
+}
+
+fn zip_entries<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    range: Range<usize>,
+    visit: &mut EntryVisitor,
+) -> Result<(), ExtractedJarError> {
+    let mut not_utf8 = 0;
+    // setting up zlib is a lot more work than inflating a header, so there is one for all entries
+    let mut inflate = Decompress::new(false);
+    for index in range {
+        // nothing is decompressed for this, that only happens once the entry is read
+        let entry = archive.by_index_raw(index)?;
+        if !entry.is_file() {
+            continue;
+        }
+        // zip does its best to decode these (CP437 or lossy UTF-8), which is good enough to tell classes apart.
+        // Looking them up by name wouldn't work, but that's not a problem when going by index
+        if std::str::from_utf8(entry.name_raw()).is_err() {
+            debug!(
+                "Entry {index} has a name that isn't valid UTF-8, reading it as {}",
+                entry.name()
+            );
+            not_utf8 += 1;
+        }
+        let name = entry.name().to_owned();
+        drop(entry);
+        let mut entry = ZipEntry {
+            state: EntryState::Unread(archive),
+            inflate: &mut inflate,
+            index,
+            position: 0,
+        };
+        if visit(&name, &mut entry)?.is_break() {
+            break;
+        }
+    }
+    if not_utf8 > 0 {
+        warn!(
+            "{not_utf8} entries have names that aren't valid UTF-8 (probably a legacy encoding), names in the output might look garbled"
+        );
+    }
+    Ok(())
+}
+
+/// All that is needed of most classes, see [`ZipEntry`]
+const HEADER_LEN: usize = 8;
+
+/// An entry of a zip that only decompresses what is actually read. The first [`HEADER_LEN`] bytes are inflated
+/// without a whole decompressing reader, which is a lot less work for the many classes that only need their header.
+/// Reading any further goes through [`ZipArchive::by_index`]
+struct ZipEntry<'a, R: Read> {
+    state: EntryState<'a, R>,
+    inflate: &'a mut Decompress,
+    index: usize,
+    /// how much was read so far
+    position: usize,
+}
+
+enum EntryState<'a, R: Read> {
+    Unread(&'a mut ZipArchive<R>),
+    /// `None` if this isn't an entry it's possible to get the header of on the cheap
+    Header(&'a mut ZipArchive<R>, Option<Vec<u8>>),
+    /// boxed, as it's a lot bigger than everything else and only needed for some entries
+    Full(Box<ZipFile<'a, R>>),
+    /// opening the entry failed
+    Broken,
+}
+
+impl<R: Read + Seek> ZipEntry<'_, R> {
+    /// Inflates just the header of the entry, if it's stored or deflated
+    fn header(
+        archive: &mut ZipArchive<R>,
+        index: usize,
+        inflate: &mut Decompress,
+    ) -> io::Result<Option<Vec<u8>>> {
+        let mut raw = archive.by_index_raw(index)?;
+        if raw.encrypted() {
+            return Ok(None);
+        }
+        let mut header = vec![0; HEADER_LEN];
+        match raw.compression() {
+            CompressionMethod::Stored => {
+                let read = (&mut raw).take(HEADER_LEN as u64).read(&mut header)?;
+                header.truncate(read);
+                Ok(Some(header))
+            }
+            CompressionMethod::Deflated => {
+                // a dynamic block starts with its Huffman tables, which usually fit into this
+                let mut input = [0; 512];
+                inflate.reset(false);
+                loop {
+                    let read = raw.read(&mut input)?;
+                    let start = inflate.total_in();
+                    let status = inflate.decompress(
+                        &input[..read],
+                        &mut header[inflate.total_out() as usize..],
+                        FlushDecompress::None,
+                    );
+                    let produced = inflate.total_out() as usize;
+                    match status {
+                        Ok(Status::StreamEnd) => break header.truncate(produced),
+                        Ok(_) if produced == HEADER_LEN => break,
+                        // leave broken data to the real thing, which knows how to complain about it
+                        Ok(_) if read > 0 && inflate.total_in() > start => {}
+                        _ => return Ok(None),
+                    }
+                }
+                Ok(Some(header))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for ZipEntry<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if matches!(self.state, EntryState::Unread(_))
+            && let EntryState::Unread(archive) = mem::replace(&mut self.state, EntryState::Broken)
+        {
+            let header = Self::header(archive, self.index, self.inflate)?;
+            self.state = EntryState::Header(archive, header);
+        }
+        if let EntryState::Header(_, Some(header)) = &self.state
+            && self.position < header.len()
+        {
+            let read = (header.len() - self.position).min(buf.len());
+            buf[..read].copy_from_slice(&header[self.position..self.position + read]);
+            self.position += read;
+            return Ok(read);
+        }
+        if matches!(self.state, EntryState::Header(..))
+            && let EntryState::Header(archive, _) =
+                mem::replace(&mut self.state, EntryState::Broken)
+        {
+            let mut file = archive.by_index(self.index)?;
+            io::copy(&mut (&mut file).take(self.position as u64), &mut io::sink())?;
+            self.state = EntryState::Full(Box::new(file));
+        }
+        match &mut self.state {
+            EntryState::Full(file) => file.read(buf),
+            _ => Err(io::Error::other("failed to open the entry before")),
+        }
+    }
+}
+
+/// Picks the right [`ArchiveReader`] for the given file by looking at its magic bytes, using the extension as a last resort.
+pub fn open_archive(file: &str) -> Result<Box<dyn ArchiveReader>, ExtractedJarError> {
+    let extension_says_tar = is_tar(file);
+    let file = File::open(file)?;
+    trace!("Reading archive at {file:?}");
+    archive_from_reader(file, extension_says_tar)
+}
+
+/// Same as [`open_archive`], for archives that aren't files of their own
+pub fn archive_from_reader<R: Read + Seek + 'static>(
+    mut file: R,
+    extension_says_tar: bool,
+) -> Result<Box<dyn ArchiveReader>, ExtractedJarError> {
+    // enough to get to the ustar magic
+    let mut buffer = Vec::with_capacity(262);
+    let read_bytes = (&mut file).take(262).read_to_end(&mut buffer)?;
+    file.rewind()?;
+    if read_bytes < 4 {
+        return Err(ExtractedJarError::InsufficientBytes(read_bytes));
+    }
+
+    if buffer.starts_with(&MAGIC_ZIP_HEADER) {
+        trace!("Archive is a zip");
+        Ok(Box::new(ZipArchive::new(file)?))
+    } else if has_tar_magic(&buffer) || extension_says_tar {
+        // old tars don't have a magic at all, so the extension is all there is to go by
+        trace!("Archive is a tar");
+        Ok(Box::new(tar::open_tar(file)?))
+    } else if buffer.starts_with(&MAGIC_7Z_HEADER) {
+        trace!("Archive is a 7z");
+        Ok(Box::new(SevenZArchive::new(file)?))
+    } else {
+        Err(ExtractedJarError::NotAJar)
+    }
+}
+
+/// Whether the first 262 bytes of a file say it's a (possibly gzipped) tar. Old tars don't have anything to tell
+/// them apart, see [`is_tar`]
+pub fn has_tar_magic(head: &[u8]) -> bool {
+    head.starts_with(&tar::MAGIC_GZIP_HEADER) || head.get(257..262) == Some(b"ustar")
+}
+
+/// Web (`.war`) and enterprise (`.ear`) archives are zips just like jars, but with the jars they need inside of them.
+/// So are Android libraries (`.aar`), whose classes are in a `classes.jar` (and `libs/*.jar`) inside
+pub fn is_bundle(file: &str) -> bool {
+    [".war", ".ear", ".aar"]
+        .iter()
+        .any(|extension| file.ends_with(extension))
+}
+
+/// Tar archives are special, as `.tar.gz` has an extension of `gz` as far as [`Path::extension`](std::path::Path::extension) is concerned
+pub fn is_tar(file: &str) -> bool {
+    [".tar", ".tar.gz", ".tgz"]
+        .iter()
+        .any(|extension| file.ends_with(extension))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ExtractedJar, JavaClass, ScanOptions,
+        sevenz::tests::sevenz_with,
+        tar::tests::{gzip, tar_with},
+        tests::{classes_of, jar_with, java_class},
+    };
+    use std::io::Cursor;
+
+    const CLASS_JAVA_11: [u8; 8] = [202, 254, 186, 190, 0, 0, 0, 55];
+
+    fn classes_in(archive: Vec<u8>, extension_says_tar: bool) -> Vec<JavaClass> {
+        let mut archive = archive_from_reader(Cursor::new(archive), extension_says_tar).unwrap();
+        classes_of(&ExtractedJar::from_archive(&mut *archive, &ScanOptions::default()).unwrap())
+    }
+
+    #[test]
+    fn test_archive_from_reader_zip() {
+        let jar = jar_with(&[("Foo.class", &CLASS_JAVA_11)]);
+        assert_eq!(classes_in(jar, false), vec![java_class(55)]);
+    }
+
+    #[test]
+    fn test_archive_from_reader_tar() {
+        let tar = tar_with(&[("Foo.class", &CLASS_JAVA_11)]);
+        assert_eq!(classes_in(tar.clone(), false), vec![java_class(55)]);
+        assert_eq!(classes_in(gzip(&tar), false), vec![java_class(55)]);
+    }
+
+    #[test]
+    fn test_archive_from_reader_7z() {
+        let archive = sevenz_with(&[("Foo.class", &CLASS_JAVA_11)]);
+        assert_eq!(classes_in(archive, false), vec![java_class(55)]);
+    }
+
+    #[test]
+    fn test_zip_slices() {
+        let names: Vec<_> = (0..10).map(|i| format!("Foo{i}.class")).collect();
+        let mut entries: Vec<(&str, &[u8])> = names
+            .iter()
+            .map(|name| (name.as_str(), &CLASS_JAVA_11[..]))
+            .collect();
+        entries.insert(
+            4,
+            ("META-INF/MANIFEST.MF", b"Implementation-Version: 1.0\n"),
+        );
+        entries[7].1 = &[202, 254, 186, 190, 0, 0, 0, 61];
+        let jar = jar_with(&entries);
+        let open = || Ok(Cursor::new(jar.clone()));
+        assert_eq!(zip_slices(open, 1, 0).unwrap()[0].range, 0..11);
+        assert_eq!(zip_slices(open, 4, 100).unwrap().len(), 1);
+
+        let slices = zip_slices(open, 4, 0).unwrap();
+        let ranges: Vec<_> = slices.iter().map(|slice| slice.range.clone()).collect();
+        assert_eq!(ranges, vec![0..3, 3..6, 6..9, 9..11]);
+        let options = ScanOptions {
+            manifest_version: Some(crate::Regex::new(".*").unwrap()),
+            ..Default::default()
+        };
+        let progress = Progress::new("app.jar", 11);
+        let parallel = ExtractedJar::from_slices(slices, false, &options, Some(&progress)).unwrap();
+        assert_eq!(progress.done(), 11);
+        let sequential = ExtractedJar::from_archive(
+            &mut ZipArchive::new(Cursor::new(jar.clone())).unwrap(),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(parallel.classfiles, sequential.classfiles);
+        assert!(parallel.manifest.is_some());
+    }
+
+    #[test]
+    fn test_zip_entry_header() {
+        use std::io::Write;
+        use zip::{ZipWriter, write::SimpleFileOptions};
+
+        // not compressible, so the header doesn't come from the first few bytes of deflated data
+        let mut long = CLASS_JAVA_11.to_vec();
+        long.extend((0..5000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8));
+        let entries: [&[u8]; 4] = [&CLASS_JAVA_11, &long, &CLASS_JAVA_11[..3], &[]];
+        let mut jar = ZipWriter::new(Cursor::new(Vec::new()));
+        for method in [CompressionMethod::Stored, CompressionMethod::Deflated] {
+            for (i, data) in entries.iter().enumerate() {
+                let options = SimpleFileOptions::default().compression_method(method);
+                jar.start_file(format!("{method}/{i}"), options).unwrap();
+                jar.write_all(data).unwrap();
+            }
+        }
+        let mut archive = ZipArchive::new(Cursor::new(jar.finish().unwrap().into_inner())).unwrap();
+
+        let mut read = Vec::new();
+        archive
+            .for_each_entry(&mut |name, entry| {
+                let mut header = [0; HEADER_LEN];
+                let start = entry.read(&mut header)?;
+                let mut data = header[..start].to_vec();
+                entry.read_to_end(&mut data)?;
+                read.push((name.to_owned(), start, data));
+                Ok(ControlFlow::Continue(()))
+            })
+            .unwrap();
+        for (i, (name, start, data)) in read.iter().enumerate() {
+            let expected = entries[i % entries.len()];
+            assert_eq!(data, expected, "{name}");
+            assert_eq!(*start, expected.len().min(HEADER_LEN), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_capped() {
+        let jar = jar_with(&[("a", &[1; 100]), ("b", &[2; 101]), ("c", &[])]);
+        let scan = |max_entries, max_entry_bytes| {
+            let mut archive = ZipArchive::new(Cursor::new(jar.clone())).unwrap();
+            let mut sizes = Vec::new();
+            Capped {
+                archive: &mut archive,
+                max_entries,
+                max_entry_bytes,
+            }
+            .for_each_entry(&mut |_, entry| {
+                sizes.push(io::copy(entry, &mut io::sink())?);
+                Ok(ControlFlow::Continue(()))
+            })
+            .map(|_| sizes)
+        };
+        assert_eq!(scan(3, 101).unwrap(), vec![100, 101, 0]);
+        let error = scan(3, 100).unwrap_err();
+        assert!(matches!(&error, ExtractedJarError::TooLarge { entry, .. } if entry == "b"));
+        assert_eq!(
+            error.to_string(),
+            "b is over the limit of 100 bytes, not going any further in case it's a zip bomb"
+        );
+        let error = scan(2, 101).unwrap_err();
+        assert!(
+            matches!(&error, ExtractedJarError::TooLarge { entry, limit } if entry.is_empty() && limit == "2 entries")
+        );
+    }
+
+    /// Replaces the bytes of an entry name in both the local header and the central directory
+    fn patch_name(mut jar: Vec<u8>, from: &[u8], to: &[u8]) -> Vec<u8> {
+        let mut start = 0;
+        while let Some(found) = jar[start..]
+            .windows(from.len())
+            .position(|window| window == from)
+        {
+            let found = start + found;
+            jar[found..found + to.len()].copy_from_slice(to);
+            start = found + from.len();
+        }
+        jar
+    }
+
+    #[test]
+    fn test_archive_from_reader_non_utf8_names() {
+        // Latin-1 without the UTF-8 flag, which zip takes as CP437
+        let jar = jar_with(&[("Caf_.class", &CLASS_JAVA_11)]);
+        let jar = patch_name(jar, b"Caf_", b"Caf\xe9");
+        assert_eq!(classes_in(jar, false), vec![java_class(55)]);
+
+        // the UTF-8 flag is set for non-ASCII names, but the name is broken anyway
+        let jar = jar_with(&[("Café.class", &CLASS_JAVA_11)]);
+        let jar = patch_name(jar, "Café".as_bytes(), b"Caf\xc3\x28");
+        let mut archive = archive_from_reader(Cursor::new(jar), false).unwrap();
+        let extracted = ExtractedJar::from_archive(&mut *archive, &ScanOptions::default()).unwrap();
+        assert_eq!(extracted.classfiles.len(), 1);
+        assert!(extracted.classfiles[0].0.ends_with(".class"));
+    }
+
+    #[test]
+    fn test_entry_names() {
+        let jar = jar_with(&[("Foo.class", &CLASS_JAVA_11), ("a/b.txt", b"text")]);
+        let mut archive = archive_from_reader(Cursor::new(jar), false).unwrap();
+        let mut names = archive.entry_names().unwrap();
+        names.sort();
+        assert_eq!(names, vec!["Foo.class", "a/b.txt"]);
+
+        let tar = tar_with(&[("Foo.class", &CLASS_JAVA_11), ("a/b.txt", b"text")]);
+        let mut archive = archive_from_reader(Cursor::new(gzip(&tar)), false).unwrap();
+        assert_eq!(archive.entry_names().unwrap(), vec!["Foo.class", "a/b.txt"]);
+    }
+
+    #[test]
+    fn test_for_each_entry_every_format() {
+        let entries: [(&str, &[u8]); 3] = [
+            ("Foo.class", &CLASS_JAVA_11),
+            ("a/b.txt", b"text"),
+            ("a/Bar.class", &CLASS_JAVA_11),
+        ];
+        let zip = jar_with(&entries);
+        let tar = tar_with(&entries);
+        let sevenz = sevenz_with(&entries);
+        for archive in [zip, tar.clone(), gzip(&tar), sevenz] {
+            let mut archive = archive_from_reader(Cursor::new(archive), false).unwrap();
+            let mut read = Vec::new();
+            archive
+                .for_each_entry(&mut |name, content| {
+                    let mut bytes = Vec::new();
+                    content.read_to_end(&mut bytes)?;
+                    read.push((name.to_owned(), bytes));
+                    // nothing after the first text file is looked at
+                    Ok(if name.ends_with(".txt") {
+                        ControlFlow::Break(())
+                    } else {
+                        ControlFlow::Continue(())
+                    })
+                })
+                .unwrap();
+            read.sort();
+            assert_eq!(
+                read,
+                [
+                    ("Foo.class".to_owned(), CLASS_JAVA_11.to_vec()),
+                    ("a/b.txt".to_owned(), b"text".to_vec())
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_archive_from_reader_unknown() {
+        let result = archive_from_reader(Cursor::new(vec![1; 1024]), false);
+        assert!(matches!(result, Err(ExtractedJarError::NotAJar)));
+    }
+}
@@ -0,0 +1,376 @@
+//! Results of earlier runs on disk, so archives that didn't change since then don't have to be read again.
+//!
+//! Every archive gets a file of its own in the cache directory, named by a hash of its path. An entry only counts if
+//! the size and modification time of the archive, the options it was scanned with and the version of this tool are
+//! still the same as back then. What scanning warned about is kept along with the result, and warned about again when
+//! the result is used.
+
+use std::{
+    env,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{Level, debug, trace, warn};
+
+use crate::{Regex, ScanOptions, ScanResult, logging, write_atomically};
+
+/// Where the cache goes without `--cache-dir`, the usual place for caches of the platform
+pub fn default_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+    Some(base.join(env!("CARGO_PKG_NAME")))
+}
+
+/// Removes everything in the cache, it not existing (yet) is fine
+pub fn clear(dir: &Path) -> io::Result<()> {
+    match fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// What an archive is recognized by, the path is always the canonical one
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Key {
+    path: String,
+    size: u64,
+    /// nanoseconds since the epoch, as a string because that doesn't fit into a JSON number
+    modified: String,
+    options: String,
+}
+
+impl Key {
+    fn of(file: &str, options: &str) -> io::Result<Self> {
+        let path = fs::canonicalize(file)?;
+        let metadata = File::open(&path)?.metadata()?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        Ok(Self {
+            path: path.display().to_string(),
+            size: metadata.len(),
+            modified: modified.to_string(),
+            options: options.to_owned(),
+        })
+    }
+}
+
+/// What is in one of the files of the cache. Generic so that it can be written from references
+#[derive(Serialize, Deserialize)]
+struct Entry<K, R, W> {
+    key: K,
+    result: R,
+    /// what scanning warned about, which is just as true the next time
+    warnings: W,
+}
+
+/// FNV-1a. Unlike [`std::hash::DefaultHasher`] it hashes the same in every build, so the cache survives updating Rust
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Hash of everything in `options` that changes what scanning an archive comes up with, or whether it fails
+fn options_hash(options: &ScanOptions) -> u64 {
+    // no `..`, so anything new has to be sorted into one or the other
+    let ScanOptions {
+        manifest_version,
+        check_build_jdk,
+        stop_above,
+        keep_classes,
+        strip_entry_prefix,
+        uniform,
+        check_stripped,
+        fail_on_preview,
+        package_floors,
+        multi_release,
+        include_meta_inf,
+        include,
+        exclude,
+        nested_jars,
+        max_nesting_depth,
+        max_entry_bytes,
+        max_entries,
+        max_class_major,
+        strict,
+        // only for URLs, which aren't cached
+        download_timeout: _,
+        max_concurrent_downloads: _,
+        // only logs
+        explain: _,
+    } = options;
+    let mut hasher = Fnv::default();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    manifest_version
+        .as_ref()
+        .map(Regex::as_str)
+        .hash(&mut hasher);
+    strip_entry_prefix
+        .as_ref()
+        .map(Regex::as_str)
+        .hash(&mut hasher);
+    (
+        check_build_jdk,
+        stop_above,
+        keep_classes,
+        uniform,
+        check_stripped,
+    )
+        .hash(&mut hasher);
+    (
+        fail_on_preview,
+        package_floors,
+        multi_release,
+        include_meta_inf,
+    )
+        .hash(&mut hasher);
+    (include, exclude, nested_jars, max_nesting_depth).hash(&mut hasher);
+    (max_entry_bytes, max_entries, max_class_major, strict).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Remembers the results of scanning archives across runs. Can be shared between threads, every archive has a file of
+/// its own anyway
+#[derive(Debug)]
+pub struct Cache {
+    dir: PathBuf,
+    /// hash of the options and the version of this tool, a result for anything else doesn't fit
+    options: String,
+    hits: AtomicUsize,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf, options: &ScanOptions) -> Self {
+        Self {
+            dir,
+            options: format!("{:016x}", options_hash(options)),
+            hits: AtomicUsize::new(0),
+        }
+    }
+
+    /// How many archives didn't need to be scanned
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    fn entry(&self, key: &Key) -> PathBuf {
+        let mut hasher = Fnv::default();
+        key.path.hash(&mut hasher);
+        self.dir.join(format!("{:016x}.json", hasher.finish()))
+    }
+
+    /// Calls `scan` unless the cache has a result for `file` as it is now. Failures aren't remembered, and neither
+    /// reading nor writing the cache failing is a reason for the scan to fail, it's just slower
+    pub fn scan<E: From<io::Error>>(
+        &self,
+        file: &str,
+        scan: impl FnOnce() -> Result<ScanResult, E>,
+    ) -> Result<ScanResult, E> {
+        let key = Key::of(file, &self.options)?;
+        let entry = self.entry(&key);
+        // anything that doesn't look like what is written below is scanned again
+        let cached = fs::read_to_string(&entry)
+            .ok()
+            .and_then(|content| {
+                serde_json::from_str::<Entry<Key, ScanResult, Vec<String>>>(&content).ok()
+            })
+            .filter(|cached| cached.key == key);
+        if let Some(cached) = cached {
+            debug!(
+                "{file} didn't change since it was last scanned, using the result from the cache"
+            );
+            for warning in cached.warnings {
+                warn!("{warning}");
+            }
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(cached.result);
+        }
+        trace!("Nothing in the cache for {file}");
+        let (result, logged) = logging::capture(scan);
+        let warnings: Vec<_> = logged
+            .iter()
+            .filter(|(level, _)| *level == Level::WARN)
+            .map(|(_, line)| line.clone())
+            .collect();
+        logging::replay(logged);
+        let result = result?;
+        if let Err(e) = fs::create_dir_all(&self.dir).and_then(|_| {
+            let json = serde_json::to_string(&Entry {
+                key: &key,
+                result: &result,
+                warnings,
+            })?;
+            write_atomically(&entry, &json)
+        }) {
+            debug!(
+                "Failed to cache the result for {file} in {}: {e}",
+                entry.display()
+            );
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        Glob, JavaVersion, process_archive,
+        tests::{TempDir, jar_with},
+    };
+
+    #[test]
+    fn test_cache_scan() {
+        let dir = TempDir::new("cache");
+        let jar = dir.join("app.jar");
+        fs::write(
+            &jar,
+            jar_with(&[
+                ("Foo.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+                ("Bar.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+            ]),
+        )
+        .unwrap();
+        let jar = jar.to_str().unwrap();
+        let options = ScanOptions {
+            keep_classes: true,
+            ..Default::default()
+        };
+
+        let mut scans = 0;
+        let mut scan = |cache: &Cache| {
+            cache
+                .scan(jar, || {
+                    scans += 1;
+                    process_archive(jar, &options)
+                })
+                .unwrap()
+        };
+        let cache = Cache::new(dir.join("cache"), &options);
+        let first = scan(&cache);
+        let second = scan(&cache);
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(second.version, JavaVersion(17));
+        assert_eq!(second.lowest, JavaVersion(8));
+        assert_eq!(second.classes, first.classes);
+        assert_eq!(second.histogram, first.histogram);
+
+        // other options, other results
+        let other = Cache::new(dir.join("cache"), &ScanOptions::default());
+        scan(&other);
+        assert_eq!(other.hits(), 0);
+
+        fs::write(
+            jar,
+            jar_with(&[("Foo.class", &[202, 254, 186, 190, 0, 0, 0, 65])]),
+        )
+        .unwrap();
+        assert_eq!(scan(&cache).version, JavaVersion(21));
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(scans, 3);
+
+        clear(&dir.join("cache")).unwrap();
+        clear(&dir.join("cache")).unwrap();
+    }
+
+    #[test]
+    fn test_cache_replays_warnings() {
+        // nothing is logged (or captured) without a subscriber
+        logging::init(0, false);
+        let dir = TempDir::new("cache-warnings");
+        let jar = dir.join("preview.jar");
+        fs::write(
+            &jar,
+            jar_with(&[("Foo.class", &[202, 254, 186, 190, 255, 255, 0, 65])]),
+        )
+        .unwrap();
+        let jar = jar.to_str().unwrap();
+        let cache = Cache::new(dir.join("cache"), &ScanOptions::default());
+        let scan = || {
+            logging::capture(|| {
+                cache
+                    .scan(jar, || process_archive(jar, &ScanOptions::default()))
+                    .unwrap()
+            })
+            .1
+        };
+        let warnings = |logged: Vec<(Level, String)>| {
+            logged
+                .into_iter()
+                .filter(|(level, _)| *level == Level::WARN)
+                .collect::<Vec<_>>()
+        };
+        let first = warnings(scan());
+        assert_eq!(
+            first,
+            [(
+                Level::WARN,
+                "Foo.class was compiled with --enable-preview, only Java 21 can run it".to_owned()
+            )]
+        );
+        assert_eq!(warnings(scan()), first);
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn test_options_hash() {
+        let hash = |options| options_hash(&options);
+        let default = hash(ScanOptions::default());
+        assert_eq!(
+            hash(ScanOptions {
+                explain: true,
+                download_timeout: Some(Duration::from_secs(1)),
+                ..Default::default()
+            }),
+            default
+        );
+        for options in [
+            ScanOptions {
+                stop_above: Some(11),
+                ..Default::default()
+            },
+            ScanOptions {
+                include: vec![Glob::new("com/**").unwrap()],
+                ..Default::default()
+            },
+            ScanOptions {
+                manifest_version: Some(Regex::new("^1\\.").unwrap()),
+                ..Default::default()
+            },
+        ] {
+            assert_ne!(hash(options), default);
+        }
+
+        // the FNV-1a test vector, nothing random about it
+        let mut hasher = Fnv::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+}
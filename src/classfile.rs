@@ -0,0 +1,221 @@
+//! Walks the structure of a class file past the header, for the checks that need more than the version.
+//!
+//! See <https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html> for the layout.
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ClassFileError {
+    #[error("Class file ends unexpectedly")]
+    Truncated,
+    #[error("Unknown constant pool tag {tag} at byte {offset}")]
+    InvalidConstantPoolTag { tag: u8, offset: usize },
+}
+
+/// Attributes of a `Code` attribute that only exist for debuggers, `javac -g:none` leaves them out
+const DEBUG_ATTRIBUTES: [&str; 3] = [
+    "LineNumberTable",
+    "LocalVariableTable",
+    "LocalVariableTypeTable",
+];
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ClassFileError> {
+        let taken = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .ok_or(ClassFileError::Truncated)?;
+        self.pos += len;
+        Ok(taken)
+    }
+
+    fn u1(&mut self) -> Result<u8, ClassFileError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u2(&mut self) -> Result<u16, ClassFileError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u4(&mut self) -> Result<u32, ClassFileError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// Whether any method of the class still has line numbers or local variable tables
+pub fn has_debug_info(class: &[u8]) -> Result<bool, ClassFileError> {
+    let mut cursor = Cursor {
+        bytes: class,
+        pos: 0,
+    };
+    // magic, minor and major version
+    cursor.take(8)?;
+
+    // only the UTF-8 constants are interesting, attribute names point at them.
+    // Index 0 isn't used by the spec, so the pool is just as long as the count says
+    let count = cursor.u2()?;
+    let mut utf8 = vec![None; usize::from(count)];
+    let mut index = 1;
+    while index < count {
+        let offset = cursor.pos;
+        match cursor.u1()? {
+            1 => {
+                let len = cursor.u2()?;
+                utf8[usize::from(index)] = Some(cursor.take(len.into())?);
+            }
+            7 | 8 | 16 | 19 | 20 => {
+                cursor.take(2)?;
+            }
+            15 => {
+                cursor.take(3)?;
+            }
+            3 | 4 | 9 | 10 | 11 | 12 | 17 | 18 => {
+                cursor.take(4)?;
+            }
+            // longs and doubles take up two entries, because reasons
+            5 | 6 => {
+                cursor.take(8)?;
+                index += 1;
+            }
+            tag => return Err(ClassFileError::InvalidConstantPoolTag { tag, offset }),
+        }
+        index += 1;
+    }
+    let name_is = |index: u16, name: &str| {
+        utf8.get(usize::from(index))
+            .copied()
+            .flatten()
+            .is_some_and(|utf8| utf8 == name.as_bytes())
+    };
+
+    // access flags, this and super class
+    cursor.take(6)?;
+    let interfaces = cursor.u2()?;
+    cursor.take(usize::from(interfaces) * 2)?;
+
+    let skip_attributes = |cursor: &mut Cursor| -> Result<(), ClassFileError> {
+        for _ in 0..cursor.u2()? {
+            cursor.take(2)?;
+            let len = cursor.u4()?;
+            cursor.take(len as usize)?;
+        }
+        Ok(())
+    };
+
+    let fields = cursor.u2()?;
+    for _ in 0..fields {
+        // access flags, name and descriptor
+        cursor.take(6)?;
+        skip_attributes(&mut cursor)?;
+    }
+
+    let methods = cursor.u2()?;
+    for _ in 0..methods {
+        cursor.take(6)?;
+        for _ in 0..cursor.u2()? {
+            let name = cursor.u2()?;
+            let len = cursor.u4()?;
+            let attribute = cursor.take(len as usize)?;
+            if !name_is(name, "Code") {
+                continue;
+            }
+            let mut code = Cursor {
+                bytes: attribute,
+                pos: 0,
+            };
+            // max stack and max locals
+            code.take(4)?;
+            let code_len = code.u4()?;
+            code.take(code_len as usize)?;
+            let exceptions = code.u2()?;
+            code.take(usize::from(exceptions) * 8)?;
+            for _ in 0..code.u2()? {
+                let name = code.u2()?;
+                if DEBUG_ATTRIBUTES.iter().any(|debug| name_is(name, debug)) {
+                    return Ok(true);
+                }
+                let len = code.u4()?;
+                code.take(len as usize)?;
+            }
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A class with a single method, whose `Code` attribute has the given attribute in it (if any)
+    pub(crate) fn class_with_code_attribute(attribute: Option<&str>) -> Vec<u8> {
+        let utf8 = |class: &mut Vec<u8>, s: &str| {
+            class.push(1);
+            class.extend((s.len() as u16).to_be_bytes());
+            class.extend(s.as_bytes());
+        };
+        let mut class = vec![202, 254, 186, 190, 0, 0, 0, 52];
+        // constant pool: #1 Code, #2 the debug attribute, #3 a long to make sure those are counted twice, #5 Class #6, #6 Foo
+        class.extend(7u16.to_be_bytes());
+        utf8(&mut class, "Code");
+        utf8(&mut class, attribute.unwrap_or("Unused"));
+        class.push(5);
+        class.extend(42u64.to_be_bytes());
+        class.extend([7, 0, 6]);
+        utf8(&mut class, "Foo");
+        // access flags, this, super, no interfaces, no fields
+        class.extend([0, 33, 0, 5, 0, 5, 0, 0, 0, 0]);
+        // one method with one attribute
+        class.extend([0, 1, 0, 1, 0, 6, 0, 6, 0, 1]);
+        let mut code = vec![0, 1, 0, 1, 0, 0, 0, 1, 177, 0, 0];
+        match attribute {
+            Some(_) => {
+                code.extend([0, 1, 0, 2, 0, 0, 0, 6, 0, 1, 0, 0, 0, 1]);
+            }
+            None => code.extend([0, 0]),
+        }
+        class.extend([0, 1]);
+        class.extend((code.len() as u32).to_be_bytes());
+        class.extend(code);
+        // no class attributes
+        class.extend([0, 0]);
+        class
+    }
+
+    #[test]
+    fn test_has_debug_info() {
+        assert_eq!(
+            has_debug_info(&class_with_code_attribute(Some("LineNumberTable"))),
+            Ok(true)
+        );
+        assert_eq!(
+            has_debug_info(&class_with_code_attribute(Some("LocalVariableTable"))),
+            Ok(true)
+        );
+        assert_eq!(has_debug_info(&class_with_code_attribute(None)), Ok(false));
+    }
+
+    #[test]
+    fn test_has_debug_info_invalid() {
+        let class = class_with_code_attribute(None);
+        assert!(matches!(
+            has_debug_info(&class[..class.len() - 5]),
+            Err(ClassFileError::Truncated)
+        ));
+        let mut class = class;
+        class[10] = 99;
+        assert_eq!(
+            has_debug_info(&class),
+            Err(ClassFileError::InvalidConstantPoolTag {
+                tag: 99,
+                offset: 10
+            })
+        );
+    }
+}
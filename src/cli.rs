@@ -1,58 +1,809 @@
-use std::sync::Mutex;
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
 
-use clap::{arg, command, parser::MatchesError, value_parser};
+use clap::{
+    ArgAction, Command, ValueEnum, arg,
+    builder::PossibleValue,
+    command,
+    parser::{MatchesError, ValueSource},
+    value_parser,
+};
+use regex::Regex;
 use thiserror::Error;
 
+use crate::{
+    JavaVersion, ParseJavaVersionError,
+    config::{Config, ConfigError},
+    exit::EXIT_CODES_HELP,
+    floor::PackageFloor,
+    glob::Glob,
+    policy::Policy,
+    verify::Verify,
+};
+
 #[derive(Error, Debug)]
 pub enum CliError {
     #[error("Failed to parse commandline arguments")]
     Parse(#[from] MatchesError),
     #[error("Did not find any valid paths")]
     NoPaths,
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// alphabetically by path
+    Path,
+    /// ascending by version
+    Version,
+    /// descending by version, the highest at the top
+    VersionDesc,
+    /// files over `--max` first, the furthest over the limit at the top
+    OverMax,
+}
+
+impl ValueEnum for SortOrder {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Path, Self::Version, Self::VersionDesc, Self::OverMax]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Self::Path => PossibleValue::new("path"),
+            Self::Version => PossibleValue::new("version"),
+            Self::VersionDesc => PossibleValue::new("version-desc"),
+            Self::OverMax => PossibleValue::new("over-max").help("requires --max"),
+        })
+    }
+}
+
+/// How the results of all files collapse into what is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    /// only the highest version of all files
+    Max,
+    /// only the lowest version of all files
+    Min,
+    /// every file on its own
+    List,
+    /// the classes above the maximum of all files, by version and then file
+    Classes,
+}
+
+impl ValueEnum for Aggregate {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Max, Self::Min, Self::List, Self::Classes]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Self::Max => PossibleValue::new("max").help("only the highest version of all files"),
+            Self::Min => PossibleValue::new("min").help("only the lowest version of all files"),
+            Self::List => PossibleValue::new("list").help("every file with its version"),
+            Self::Classes => PossibleValue::new("classes").help(
+                "the classes above --max of all files, by version and then file. Says how many different classes that actually are",
+            ),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    /// nested by package, for treemap visualizations
+    TreemapJson,
+    /// for the textfile collector of the Prometheus node exporter
+    Prometheus,
+}
+
+impl ValueEnum for OutputFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Text, Self::Json, Self::TreemapJson, Self::Prometheus]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Self::Text => PossibleValue::new("text"),
+            Self::Json => PossibleValue::new("json"),
+            Self::TreemapJson => PossibleValue::new("treemap-json"),
+            Self::Prometheus => PossibleValue::new("prometheus"),
+        })
+    }
+}
+
+/// Where one of the outputs goes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Destination {
+    Stdout,
+    File(PathBuf),
+}
+
+/// One `--report FORMAT:DESTINATION`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportTarget {
+    pub format: OutputFormat,
+    pub destination: Destination,
+}
+
+fn parse_java_version(version: &str) -> Result<u16, ParseJavaVersionError> {
+    version.parse::<JavaVersion>().map(|version| *version)
+}
+
+/// A file that is going to be written after scanning
+fn parse_output_path(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+    // better to find out now than after scanning everything
+    if let Some(parent) = path.parent().filter(|parent| parent != &Path::new(""))
+        && !parent.is_dir()
+    {
+        return Err(format!("directory {} does not exist", parent.display()));
+    }
+    Ok(path)
+}
+
+fn parse_report_target(value: &str) -> Result<ReportTarget, String> {
+    let (format, destination) = value
+        .split_once(':')
+        .ok_or("expected FORMAT:DESTINATION, e.g. json:report.json")?;
+    let format = OutputFormat::from_str(format, false)?;
+    let destination = match destination {
+        "" => return Err("missing destination, use - for stdout".to_owned()),
+        "-" => Destination::Stdout,
+        path => Destination::File(parse_output_path(path)?),
+    };
+    Ok(ReportTarget {
+        format,
+        destination,
+    })
 }
 
 #[derive(Debug)]
 pub struct Cli {
     pub files: Vec<String>,
+    /// scan directories in `files` as well
+    pub recursive: bool,
     pub max: Option<u16>,
+    /// whether `max` itself is too high already
+    pub max_exclusive: bool,
+    pub min: Option<u16>,
+    /// use the version of the installed java as `max`
+    pub compare_to_runtime: bool,
+    /// a file whose version is the maximum, or `max` if that is lower
+    pub baseline: Option<String>,
+    pub sort: Option<SortOrder>,
+    pub aggregate: Aggregate,
+    pub group_by_version: bool,
+    /// a table of all files in the end
+    pub summary: bool,
+    /// how many classes of every version there are, per file
+    pub histogram: bool,
+    /// how many files there are of every version, for all of them
+    pub count: bool,
+    /// the lowest and the highest version, per file
+    pub range: bool,
+    /// every class with its major version
+    pub list: bool,
+    /// nothing but the version of every file, one per line
+    pub version_only: bool,
+    /// the class file major version instead of the Java version for `version_only`
+    pub raw_major: bool,
+    pub limit_per_version: Option<usize>,
+    pub require_manifest_version: Option<Regex>,
+    /// compare the JDK in the manifest to the classes
+    pub check_manifest: bool,
+    /// removed from the start of entry names in the output
+    pub entry_regex_strip: Option<Regex>,
+    pub format: OutputFormat,
+    /// if set, these are used instead of `format`
+    pub reports: Vec<ReportTarget>,
+    /// where to put a summary, no matter what the format is
+    pub batch_summary_json: Option<PathBuf>,
+    pub full_scan: bool,
+    /// only scan archives with the same content once
+    pub dedupe_by_content: bool,
+    /// print the schema of the JSON output, instead of doing anything else
+    pub json_schema: bool,
+    /// draw a bar while scanning large archives
+    pub progress: bool,
+    /// check again whenever a file changes, until stopped
+    pub watch: bool,
+    /// how many files are scanned at the same time
+    pub jobs: usize,
+    /// scan archives even if there is a result for them in the cache
+    pub no_cache: bool,
+    pub clear_cache: bool,
+    pub cache_dir: Option<PathBuf>,
+    /// list the classes instead of reading them
+    pub names_only: bool,
+    pub multi_release: bool,
+    /// count the classes anywhere in META-INF
+    pub include_meta_inf: bool,
+    /// entries of archives that count, all of them if empty
+    pub include: Vec<Glob>,
+    /// entries of archives that don't count, no matter what `include` says
+    pub exclude: Vec<Glob>,
+    pub policies: Vec<Policy>,
+    pub package_floors: Vec<PackageFloor>,
+    /// dump this many bytes of every input, for debugging
+    pub head_bytes: Option<u64>,
+    pub fail_if_any_preview: bool,
+    /// fail in the end if there are any preview classes, listing all of them
+    pub fail_on_preview: bool,
+    /// how long scanning a single archive may take
+    pub scan_timeout: Option<Duration>,
+    /// how long downloading an input that is a URL may take
+    pub timeout: Option<Duration>,
+    /// how many inputs that are URLs may be downloaded at the same time
+    pub concurrency_limit: Option<usize>,
+    /// fail for files that were skipped, e.g. because they couldn't be read
+    pub fail_on_error: bool,
+    /// scan jars inside of jars as well
+    pub nested_jars: bool,
+    pub max_nesting_depth: usize,
+    /// limits for archives, against zip bombs
+    pub max_entry_bytes: u64,
+    pub max_entries: usize,
+    /// major versions above this are broken classes
+    pub max_class_major: u16,
+    /// say how the kind of every input was figured out
+    pub explain: bool,
+    /// only scan files that are named like what they are
+    pub strict: bool,
+    /// scan everything before failing for files that couldn't be scanned
+    pub keep_going: bool,
+    /// stop at the first file that is above the maximum
+    pub fail_fast: bool,
+    pub check_stripped: bool,
+    /// report the real path of inputs that are symlinks
+    pub resolve_symlinks: bool,
+    /// fail for jars with classes of more than one version
+    pub assert_uniform_per_jar: bool,
+    /// print which JDK is needed to run everything
+    pub recommend_jdk: bool,
+    /// recommend the closest LTS release instead of just the minimum
+    pub lts: bool,
+    /// leave files that aren't above the maximum out of structured output
+    pub only_violations: bool,
+    /// list jars without classes instead of failing on them
+    pub report_zero_class_jars: bool,
+    /// the paths are earlier JSON reports to put together
+    pub merge: bool,
+    /// leave the suggestion on how to fix things out of the --max failure
+    pub no_hint: bool,
+    pub ignore_paths: Vec<Glob>,
+    /// base to compare against if only changed files should be scanned
+    pub changed_since: Option<String>,
+    /// set if the `verify` subcommand is used instead of the default behaviour
+    pub verify: Option<Verify>,
+    /// how many times `-v` was given
+    pub verbosity: u8,
+    /// don't log anything at all
+    pub quiet: bool,
+    /// stdout gets structured output, so nothing else may go there
+    pub structured_stdout: bool,
 }
 
-pub static LOG_LEVEL: Mutex<u8> = Mutex::new(0);
-
 impl Cli {
     pub fn new() -> Result<Self, CliError> {
+        Self::from_args(env::args_os())
+    }
+
+    /// Same as [`Cli::new`], with the arguments given instead of taken from the process. The first one is the binary
+    pub fn from_args<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(
+        args: I,
+    ) -> Result<Self, CliError> {
         let matches = command!()
+            .after_help(EXIT_CODES_HELP)
+            // files named `verify` still work as `./verify`
+            .args_conflicts_with_subcommands(true)
+            .subcommand_negates_reqs(true)
+            .subcommand(
+                Command::new("verify")
+                    .about("check that the given files are built for the expected version, e.g. `verify --expect 17 app.jar`")
+                    .arg(
+                        arg!(--expect <VERSION> "the version the files have to be built for (or lower, unless --exact is set)")
+                            .required(true)
+                            .value_parser(parse_java_version)
+                    )
+                    .arg(arg!(--exact "lower versions than --expect fail as well"))
+                    .arg(
+                        arg!(<path> ... "files to read, - to read a class or an archive from stdin, http:// or https:// URLs to download one")
+                            .trailing_var_arg(true)
+                            .required(true)
+                            .value_parser(value_parser!(String)),
+                    )
+            )
+            .arg(
+                arg!(-r --recursive "scan everything (class files, jars, tars, 7z archives) inside of directories that are passed as paths")
+            )
             .arg(
                 arg!(-m --max <MAXIMUM> "maximum version that is supported by your use case. A version higher than that will result in an exit code > 0")
                     .required(false)
-                    .value_parser(value_parser!(u16))
+                    .value_parser(parse_java_version)
             )
             .arg(
-                arg!(<path> ... "files to read")
+                arg!(--"max-exclusive" "the maximum itself is too high as well, --max 17 only allows up to Java 16")
+                    .overrides_with("max-inclusive")
+            )
+            .arg(
+                arg!(--"max-inclusive" "the maximum itself is still fine, the default. Undoes an earlier --max-exclusive")
+                    .overrides_with("max-exclusive")
+            )
+            .arg(
+                arg!(-n --min <MINIMUM> "minimum version that is supported by your use case. A class older than that will result in an exit code > 0")
+                    .required(false)
+                    .value_parser(parse_java_version)
+            )
+            .arg(
+                arg!(--"compare-to-runtime" "use the version of the installed java (on the PATH, or in JAVA_HOME) as --max, to check if it can run everything")
+                    .conflicts_with("max")
+            )
+            .arg(
+                arg!(--baseline <PATH> "scan this file first and use its version as --max, so nothing can be newer than an artifact that is known to be fine. The lower one wins if --max (or --compare-to-runtime) is given as well")
+                    .required(false)
+            )
+            .arg(
+                arg!(<path> ... "files to read, - to read a class or an archive from stdin, http:// or https:// URLs to download one")
                     .trailing_var_arg(true)
-                    .required(true)
+                    .required(false)
+                    .required_unless_present_any(["clear-cache", "json-schema"])
                     .value_parser(value_parser!(String)),
             )
             .arg(
-                arg!(-v --verbose ... "verbose logging. can be set multiple times")
-)
-            .get_matches();
+                arg!(-v --verbose ... "verbose logging (to stderr). can be set multiple times. RUST_LOG works like for anything else that uses tracing and wins over this: RUST_LOG=debug is the same as -v, warn leaves out everything but warnings (the results too), off or error is the same as --quiet")
+                    .global(true)
+            )
+            .arg(
+                arg!(-q --quiet "don't log anything, not even the results or warnings, for when only the exit code matters. The error that fails the run is still printed")
+                    .global(true)
+                    .conflicts_with("verbose")
+            )
+            .arg(
+                arg!(--sort <ORDER> "print a report of all files sorted in the given order after processing them. Files that are equal keep the order they were given in")
+                    .required(false)
+                    .value_parser(value_parser!(SortOrder))
+                    .requires_if("over-max", "max")
+            )
+            .arg(
+                arg!(--aggregate <MODE> "how the results of all files are put together, for text and json output")
+                    .required(false)
+                    .default_value("list")
+                    .value_parser(value_parser!(Aggregate))
+                    .conflicts_with_all(["sort", "group-by-version"])
+            )
+            .arg(
+                arg!(--"group-by-version" "print a report of all files, listed under their version, after processing them")
+            )
+            .arg(
+                arg!(--"summary" "print a table of all files with their type, number of classes and version after processing them")
+                    .conflicts_with_all(["group-by-version", "aggregate"])
+            )
+            .arg(
+                arg!(--count "print one line with how many files there are of every version in the end, e.g. `Java 1.8: 12 files, Java 17: 1 file`. Counts every class of an archive instead with --list")
+            )
+            .arg(
+                arg!(--"version-only" "print nothing but the version of every file, one per line in the order of the files, e.g. `8` for Java 1.8. For scripts, everything else is only logged as with --quiet")
+                    .conflicts_with_all(["format", "report", "sort", "aggregate", "group-by-version", "summary", "names-only", "merge", "watch", "progress"])
+            )
+            .arg(
+                arg!(--"raw-major" "print the major version of the class files (e.g. `52` for Java 1.8) with --version-only")
+                    .requires("version-only")
+            )
+            .arg(
+                arg!(--"histogram" "print how many classes of every major version there are for each file, e.g. `52 (Java 1.8): 1200 class(es)`")
+            )
+            .arg(
+                arg!(--"range" "print the lowest and the highest version of the classes for each file, e.g. `min Java 1.8, max Java 17`")
+            )
+            .arg(
+                arg!(--list "print every class of every file with its major version, sorted by name, e.g. `com/example/Foo.class 52`")
+                    .conflicts_with("names-only")
+            )
+            .arg(
+                arg!(--"limit-per-version" <N> "only list the first N files per version with --group-by-version")
+                    .required(false)
+                    .value_parser(value_parser!(usize))
+                    .requires("group-by-version")
+            )
+            .arg(
+                arg!(--"require-manifest-version" <REGEX> "fail if the Implementation-Version in the manifest of a jar doesn't match the given pattern")
+                    .required(false)
+                    .value_parser(Regex::new)
+            )
+            .arg(
+                arg!(--"check-manifest" "warn if the JDK that built a jar according to its manifest (Build-Jdk-Spec or Build-Jdk) isn't the version of its classes")
+            )
+            .arg(
+                arg!(--"entry-regex-strip" <REGEX> "remove whatever the pattern matches at the start of entry names in the output, e.g. `BOOT-INF/classes/` for Spring Boot jars")
+                    .required(false)
+                    .value_parser(Regex::new)
+            )
+            .arg(
+                arg!(-f --format <FORMAT> "output format. Anything but text only prints the result to stdout, everything else goes to stderr")
+                    .required(false)
+                    .default_value("text")
+                    .value_parser(value_parser!(OutputFormat))
+            )
+            .arg(
+                arg!(--report <"FORMAT:DESTINATION"> "where to write the result in which format, - being stdout, e.g. `--report text:- --report json:out.json`. Can be given multiple times, replaces --format")
+                    .required(false)
+                    .action(ArgAction::Append)
+                    .value_parser(parse_report_target)
+                    .conflicts_with("format")
+            )
+            .arg(
+                arg!(--"batch-summary-json" <PATH> "also write a summary (lowest and highest version, files per version, violations) as JSON to the given file, no matter what --format is")
+                    .required(false)
+                    .value_parser(parse_output_path)
+            )
+            .arg(
+                arg!(--"ignore-path" <GLOB> "skip input files whose path matches the glob. Only applies to the paths that are passed in, never to entries inside of archives. Can be given multiple times")
+                    .required(false)
+                    .action(ArgAction::Append)
+                    .value_parser(Glob::new)
+            )
+            .arg(
+                arg!(--"changed-only" "only scan inputs that git reports as changed (compared to --base-ref). Inputs that are directories are scanned if anything inside of them changed")
+            )
+            .arg(
+                arg!(--"base-ref" <REF> "what to compare against for --changed-only")
+                    .required(false)
+                    .default_value("HEAD")
+                    .value_parser(value_parser!(String))
+            )
+            .arg(
+                arg!(--"no-hint" "don't suggest how to rebuild when something is above --max, for terse logs")
+            )
+            .arg(
+                arg!(--"check-stripped" "count the classes that still have debug info (line numbers, local variable names) in them. Reads every class completely, so this is a lot slower")
+            )
+            .arg(
+                arg!(--"resolve-symlink-report" "also report the file that was actually read for inputs that are symlinks (or are in a symlinked directory)")
+            )
+            .arg(
+                arg!(--"assert-uniform-per-jar" "fail if the classes of a jar don't all have the same version, which they would if they were compiled together. Jars inside of a tar are checked one by one")
+            )
+            .arg(
+                arg!(--"recommend-jdk" "print which JDK is needed to run everything that was scanned")
+            )
+            .arg(
+                arg!(--lts "also recommend the closest LTS release with --recommend-jdk")
+                    .requires("recommend-jdk")
+            )
+            .arg(
+                arg!(--"only-violations" "only put the files above --max into structured output (JSON), together with a summary of everything that was scanned")
+            )
+            .arg(
+                arg!(--"report-zero-class-jars" "list the jars that don't contain any class files at all instead of failing on them")
+            )
+            .arg(
+                arg!(--merge "treat the paths as JSON reports of earlier runs (--format json) and merge them instead of scanning anything. --max applies to the merged result")
+            )
+            .arg(
+                arg!(--"full-scan" "always read every class of an archive. Without this, an archive is only read until a class above --max shows up, unless its highest version is needed for the output")
+            )
+            .arg(
+                arg!(--policy <"NAME:MAX[:GLOB]"> "a named maximum for the inputs matching the glob (all of them without one), e.g. `--policy app:17:app/**/*.jar --policy deps:21`. Every policy gets a verdict, the run fails if any of them fails. Can be given multiple times")
+                    .required(false)
+                    .action(ArgAction::Append)
+                    .value_parser(Policy::parse)
+            )
+            .arg(
+                arg!(--"package-floor" <"PACKAGE:MIN"> "fail for archives with classes in the package (or below it) that are older than MIN, e.g. `--package-floor com.acme.api:17`. The most specific package wins. Can be given multiple times, and adds to the [package-floor] table of the config")
+                    .required(false)
+                    .action(ArgAction::Append)
+                    .value_parser(PackageFloor::parse)
+            )
+            .arg(
+                arg!(--"head-bytes" <N> "print the first N bytes of every input to stderr, to figure out why a file isn't recognized")
+                    .required(false)
+                    .hide(true)
+                    .value_parser(value_parser!(u64))
+            )
+            .arg(
+                arg!(--"multi-release" "also count the classes under META-INF/versions/<n>/ of multi-release jars, so the version is the highest one that is actually shipped")
+            )
+            .arg(
+                arg!(--include <GLOB> "only count the classes in archives whose entry path matches one of these globs, e.g. `com/example/**`. Can be given multiple times")
+                    .required(false)
+                    .action(ArgAction::Append)
+                    .value_parser(Glob::new)
+            )
+            .arg(
+                arg!(--exclude <GLOB> "leave out the classes in archives whose entry path matches the glob, e.g. `com/example/test/**`. Wins over --include. Can be given multiple times")
+                    .required(false)
+                    .action(ArgAction::Append)
+                    .value_parser(Glob::new)
+            )
+            .arg(
+                arg!(--"include-meta-inf" "also count any other classes under META-INF/, which are left out by default. Includes what --multi-release would count")
+            )
+            .arg(
+                arg!(--"names-only" "only list the class files in the given archives, without reading them. Very fast, but there are no versions in this mode, so there is nothing to check either")
+                    .conflicts_with_all(["max", "compare-to-runtime", "baseline", "sort", "format", "report", "batch-summary-json", "merge", "policy"])
+            )
+            .arg(
+                arg!(--"dedupe-by-content" "only scan archives with identical content once, reporting the result for every path. Saves time when the same jar shows up all over the place")
+            )
+            .arg(
+                arg!(--"json-schema" "print a JSON Schema of what --format json prints and exit, without reading any files")
+            )
+            .arg(
+                arg!(--progress "show a progress bar on stderr while scanning large jars. Only if stdout and stderr are terminals and the output is text")
+            )
+            .arg(
+                arg!(--watch "keep running and check the files again whenever they change, printing a line for every check. Directories are only looked at once, new files in there aren't picked up. Stop with Ctrl-C")
+                    .conflicts_with_all(["names-only", "merge", "format", "report", "batch-summary-json", "aggregate"])
+            )
+            .arg(
+                arg!(-j --jobs <N> "how many files are scanned at the same time, as many as there are cores by default. The output is the same as if they were scanned one after the other")
+                    .required(false)
+                    .value_parser(value_parser!(u64).range(1..))
+            )
+            .arg(
+                arg!(--"no-cache" "scan every archive, even if it didn't change since the last run. The results are still cached for the next one")
+            )
+            .arg(
+                arg!(--"clear-cache" "remove all cached results before scanning anything. Paths are optional with this")
+            )
+            .arg(
+                arg!(--"cache-dir" <DIR> "where to cache the results of scanning archives. Defaults to java-classfile-version in the cache directory of the user, like ~/.cache")
+                    .required(false)
+                    .value_parser(value_parser!(PathBuf))
+            )
+            .arg(
+                arg!(--"scan-timeout" <SECONDS> "give up on archives that take longer than this to scan, e.g. because they are broken in weird ways. They are skipped, see --fail-on-error")
+                    .required(false)
+                    .value_parser(value_parser!(u64).range(1..))
+            )
+            .arg(
+                arg!(--timeout <SECONDS> "give up on downloading inputs that are http:// or https:// URLs after this long")
+                    .required(false)
+                    .value_parser(value_parser!(u64).range(1..))
+            )
+            .arg(
+                arg!(--"concurrency-limit" <N> "download at most this many inputs that are http:// or https:// URLs at the same time, so a server doesn't get all of --jobs at once. Local files aren't affected")
+                    .required(false)
+                    .value_parser(value_parser!(u64).range(1..))
+            )
+            .arg(
+                arg!(--"fail-on-error" "fail in the end if any file was skipped, because it timed out (--scan-timeout) or couldn't be read for lack of permissions")
+            )
+            .arg(
+                arg!(--"nested-jars" "also scan the jars inside of jars (like BOOT-INF/lib/ of Spring Boot jars or the dependencies of uber jars), and the jars inside of those. Jars inside of tars, 7z archives, wars, ears and aars are always scanned")
+            )
+            .arg(
+                arg!(--"max-nesting-depth" <N> "how many jars deep nested jars are scanned, anything deeper than that is an error")
+                    .required(false)
+                    .value_parser(value_parser!(usize))
+                    .default_value("4")
+            )
+            .arg(
+                arg!(--"max-entry-bytes" <BYTES> "fail for archives with an entry that is larger than this once decompressed, in case it's a zip bomb. Only matters for what is actually read completely, like nested jars")
+                    .required(false)
+                    .value_parser(value_parser!(u64))
+                    .default_value("268435456")
+            )
+            .arg(
+                arg!(--"max-entries" <N> "fail for archives with more entries than this, in case it's a zip bomb. Nested jars count on their own")
+                    .required(false)
+                    .value_parser(value_parser!(usize))
+                    .default_value("1000000")
+            )
+            .arg(
+                arg!(--"max-class-major" <MAJOR> "the highest major version (69 for Java 25) a class can have before it's taken to be broken instead. Only needs raising once Java versions are that far along")
+                    .required(false)
+                    .value_parser(value_parser!(u16).range(45..))
+                    .default_value("89")
+            )
+            .arg(
+                arg!(-k --"keep-going" "don't stop at the first file that can't be scanned. Everything else is scanned (and checked) first, then the run fails with a list of all files that failed")
+            )
+            .arg(
+                arg!(--"fail-fast" "stop at the first file that is above the maximum instead of scanning everything and failing with all of them. The output only has the files up to that one")
+                    .conflicts_with_all(["merge", "watch"])
+            )
+            .arg(
+                arg!(--"fail-if-any-preview" "fail as soon as a class compiled with --enable-preview shows up, including nested jars and multi-release classes of jars")
+            )
+            .arg(
+                arg!(--"fail-on-preview" "fail in the end if any of the scanned classes were compiled with --enable-preview, listing all of them. Unlike --fail-if-any-preview, this goes through everything first")
+                    .conflicts_with("fail-if-any-preview")
+            )
+            .arg(
+                arg!(--explain "log how every input was identified: by its extension or its first bytes, what it was tried as and what it turned out to be")
+            )
+            .arg(
+                arg!(--strict "fail for files that aren't named like what they are (.class, .jar, .war, .ear, .aar, .zip, .tar, .tar.gz, .tgz or .7z) instead of going by their content. Stdin and URLs still go by their content")
+            )
+            .arg(
+                arg!(--config <PATH> "read defaults for --max, --min, --recursive, --format and --package-floor from this file instead of looking for a .jcfv.toml in the current directory and its parents. Flags win over the config")
+                    .required(false)
+                    .value_parser(value_parser!(PathBuf))
+            )
+            .arg(
+                arg!(--"no-config" "don't look for a .jcfv.toml")
+                    .conflicts_with("config")
+            )
+            .get_matches_from(args);
+        let config = match matches.try_get_one::<PathBuf>("config")? {
+            Some(path) => Config::load(path)?,
+            None if matches.get_flag("no-config") => Config::default(),
+            None => {
+                let dir = env::current_dir().map_err(|e| ConfigError::IO(".".to_owned(), e))?;
+                Config::find(&dir)?.unwrap_or_default()
+            }
+        };
+        let from_command_line =
+            |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
 
-        let paths = matches.try_get_many::<String>("path")?;
-        let max = matches.try_get_one::<u16>("max")?;
+        let verify = matches.subcommand_matches("verify");
+        let paths = verify.unwrap_or(&matches).try_get_many::<String>("path")?;
+        let max = matches
+            .try_get_one::<u16>("max")?
+            .copied()
+            // the runtime is the maximum then
+            .or(config
+                .max
+                .filter(|_| !matches.get_flag("compare-to-runtime")));
+        let sort = matches.try_get_one::<SortOrder>("sort")?;
+        let require_manifest_version = matches.try_get_one::<Regex>("require-manifest-version")?;
+        let format = match config.format {
+            Some(format) if !from_command_line("format") && !from_command_line("report") => format,
+            _ => matches
+                .try_get_one::<OutputFormat>("format")?
+                .copied()
+                .unwrap_or(OutputFormat::Text),
+        };
+        let reports: Vec<ReportTarget> = matches
+            .try_get_many::<ReportTarget>("report")?
+            .map(|reports| reports.cloned().collect())
+            .unwrap_or_default();
+        let structured_stdout = format != OutputFormat::Text
+            || reports.iter().any(|report| {
+                report.destination == Destination::Stdout && report.format != OutputFormat::Text
+            });
 
-        if let Some(paths) = paths {
-            let paths: Vec<_> = paths.map(|path| path.to_owned()).collect();
-            let loglevel = matches.try_get_one::<u8>("verbose")?;
-            if let Some(loglevel) = loglevel {
-                // this should be safe?
-                let mut global_loglevel = LOG_LEVEL.lock().unwrap();
-                *global_loglevel = *loglevel;
-            }
+        // clearing the cache is something to do on its own as well
+        if paths.is_some() || matches.get_flag("clear-cache") || matches.get_flag("json-schema") {
+            let paths: Vec<_> = paths.into_iter().flatten().cloned().collect();
+            // global, so it ends up with the subcommand if there is one
+            let verbosity = verify.unwrap_or(&matches).try_get_one::<u8>("verbose")?;
+            let quiet =
+                verify.unwrap_or(&matches).get_flag("quiet") || matches.get_flag("version-only");
 
             Ok(Self {
                 files: paths,
-                max: max.copied(),
+                recursive: matches.get_flag("recursive") || config.recursive.unwrap_or(false),
+                max,
+                max_exclusive: matches.get_flag("max-exclusive"),
+                min: matches.try_get_one::<u16>("min")?.copied().or(config.min),
+                compare_to_runtime: matches.get_flag("compare-to-runtime"),
+                baseline: matches.try_get_one::<String>("baseline")?.cloned(),
+                sort: sort.copied(),
+                aggregate: matches
+                    .try_get_one::<Aggregate>("aggregate")?
+                    .copied()
+                    .unwrap_or(Aggregate::List),
+                group_by_version: matches.get_flag("group-by-version"),
+                summary: matches.get_flag("summary"),
+                histogram: matches.get_flag("histogram"),
+                count: matches.get_flag("count"),
+                range: matches.get_flag("range"),
+                list: matches.get_flag("list"),
+                version_only: matches.get_flag("version-only"),
+                raw_major: matches.get_flag("raw-major"),
+                limit_per_version: matches.try_get_one::<usize>("limit-per-version")?.copied(),
+                require_manifest_version: require_manifest_version.cloned(),
+                check_manifest: matches.get_flag("check-manifest"),
+                entry_regex_strip: matches.try_get_one::<Regex>("entry-regex-strip")?.cloned(),
+                format,
+                reports,
+                batch_summary_json: matches
+                    .try_get_one::<PathBuf>("batch-summary-json")?
+                    .cloned(),
+                full_scan: matches.get_flag("full-scan"),
+                dedupe_by_content: matches.get_flag("dedupe-by-content"),
+                json_schema: matches.get_flag("json-schema"),
+                progress: matches.get_flag("progress"),
+                watch: matches.get_flag("watch"),
+                jobs: match matches.try_get_one::<u64>("jobs")? {
+                    Some(jobs) => usize::try_from(*jobs).unwrap_or(usize::MAX),
+                    None => thread::available_parallelism().map_or(1, usize::from),
+                },
+                no_cache: matches.get_flag("no-cache"),
+                clear_cache: matches.get_flag("clear-cache"),
+                cache_dir: matches.try_get_one::<PathBuf>("cache-dir")?.cloned(),
+                names_only: matches.get_flag("names-only"),
+                multi_release: matches.get_flag("multi-release"),
+                include_meta_inf: matches.get_flag("include-meta-inf"),
+                include: matches
+                    .try_get_many::<Glob>("include")?
+                    .map(|globs| globs.cloned().collect())
+                    .unwrap_or_default(),
+                exclude: matches
+                    .try_get_many::<Glob>("exclude")?
+                    .map(|globs| globs.cloned().collect())
+                    .unwrap_or_default(),
+                package_floors: PackageFloor::merge(
+                    config.package_floors,
+                    matches
+                        .try_get_many::<PackageFloor>("package-floor")?
+                        .map(|floors| floors.cloned().collect())
+                        .unwrap_or_default(),
+                ),
+                head_bytes: matches.try_get_one::<u64>("head-bytes")?.copied(),
+                policies: matches
+                    .try_get_many::<Policy>("policy")?
+                    .map(|policies| policies.cloned().collect())
+                    .unwrap_or_default(),
+                fail_if_any_preview: matches.get_flag("fail-if-any-preview"),
+                fail_on_preview: matches.get_flag("fail-on-preview"),
+                fail_on_error: matches.get_flag("fail-on-error"),
+                keep_going: matches.get_flag("keep-going"),
+                fail_fast: matches.get_flag("fail-fast"),
+                nested_jars: matches.get_flag("nested-jars"),
+                max_nesting_depth: *matches
+                    .try_get_one::<usize>("max-nesting-depth")?
+                    .expect("has a default"),
+                max_entry_bytes: *matches
+                    .try_get_one::<u64>("max-entry-bytes")?
+                    .expect("has a default"),
+                max_entries: *matches
+                    .try_get_one::<usize>("max-entries")?
+                    .expect("has a default"),
+                explain: matches.get_flag("explain"),
+                strict: matches.get_flag("strict"),
+                max_class_major: *matches
+                    .try_get_one::<u16>("max-class-major")?
+                    .expect("has a default"),
+                scan_timeout: matches
+                    .try_get_one::<u64>("scan-timeout")?
+                    .map(|seconds| Duration::from_secs(*seconds)),
+                timeout: matches
+                    .try_get_one::<u64>("timeout")?
+                    .map(|seconds| Duration::from_secs(*seconds)),
+                concurrency_limit: matches
+                    .try_get_one::<u64>("concurrency-limit")?
+                    .map(|limit| usize::try_from(*limit).unwrap_or(usize::MAX)),
+                no_hint: matches.get_flag("no-hint"),
+                check_stripped: matches.get_flag("check-stripped"),
+                merge: matches.get_flag("merge"),
+                resolve_symlinks: matches.get_flag("resolve-symlink-report"),
+                assert_uniform_per_jar: matches.get_flag("assert-uniform-per-jar"),
+                recommend_jdk: matches.get_flag("recommend-jdk"),
+                lts: matches.get_flag("lts"),
+                only_violations: matches.get_flag("only-violations"),
+                report_zero_class_jars: matches.get_flag("report-zero-class-jars"),
+                changed_since: matches
+                    .get_flag("changed-only")
+                    .then(|| matches.try_get_one::<String>("base-ref"))
+                    .transpose()?
+                    .flatten()
+                    .cloned(),
+                ignore_paths: matches
+                    .try_get_many::<Glob>("ignore-path")?
+                    .map(|globs| globs.cloned().collect())
+                    .unwrap_or_default(),
+                verify: verify
+                    .map(|verify| -> Result<_, CliError> {
+                        Ok(verify.try_get_one::<u16>("expect")?.map(|&expect| Verify {
+                            expect,
+                            exact: verify.get_flag("exact"),
+                        }))
+                    })
+                    .transpose()?
+                    .flatten(),
+                verbosity: verbosity.copied().unwrap_or(0),
+                quiet,
+                structured_stdout,
             })
         } else {
             Err(CliError::NoPaths)
@@ -60,36 +811,45 @@ impl Cli {
     }
 }
 
-#[macro_export]
-macro_rules! log {
-    ($($arg:tt)*) => {{
-        println!($($arg)*);
-    }};
-}
-
-#[macro_export]
-macro_rules! debug {
-    ($($arg:tt)*) => {{
-        let __loglevel = $crate::cli::LOG_LEVEL.lock().unwrap();
-        if *__loglevel >= 1 {
-            println!($($arg)*);
-        }
-    }};
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-#[macro_export]
-macro_rules! trace {
-    ($($arg:tt)*) => {{
-        let __loglevel = $crate::cli::LOG_LEVEL.lock().unwrap();
-        if *__loglevel >= 2 {
-            println!($($arg)*);
-        }
-    }};
-}
+    #[test]
+    fn test_parse_java_version() {
+        assert_eq!(parse_java_version("8"), Ok(8));
+        assert_eq!(parse_java_version("1.8"), Ok(8));
+        assert_eq!(parse_java_version("17"), Ok(17));
+        assert_eq!(
+            parse_java_version("abc"),
+            Err(ParseJavaVersionError::Invalid("abc".to_owned()))
+        );
+        assert!(parse_java_version("99.99.99").is_err());
+        assert_eq!(
+            parse_java_version("52").unwrap_err().to_string(),
+            "`52` looks like a class file version, not a Java version. That would be Java 1.8, pass `1.8` for it"
+        );
+    }
 
-#[macro_export]
-macro_rules! warn {
-    ($($arg:tt)*) => {{
-        eprintln!($($arg)*);
-    }};
+    #[test]
+    fn test_parse_report_target() {
+        assert_eq!(
+            parse_report_target("text:-"),
+            Ok(ReportTarget {
+                format: OutputFormat::Text,
+                destination: Destination::Stdout
+            })
+        );
+        assert_eq!(
+            parse_report_target("json:out.json"),
+            Ok(ReportTarget {
+                format: OutputFormat::Json,
+                destination: Destination::File("out.json".into())
+            })
+        );
+        assert!(parse_report_target("json").is_err());
+        assert!(parse_report_target("json:").is_err());
+        assert!(parse_report_target("yaml:-").is_err());
+        assert!(parse_report_target("json:does/not/exist/out.json").is_err());
+    }
 }
@@ -0,0 +1,232 @@
+//! `.jcfv.toml`, defaults for the flags that are the same on every run of a project, like `--max`.
+//!
+//! Only the part of TOML that makes sense for a handful of top level settings is understood: `key = value` lines,
+//! comments, strings, integers and booleans, plus a `[package-floor]` table of packages and their minimum version.
+//! Anything else (other tables, arrays, unknown keys) is an error rather than being ignored, so a typo doesn't go
+//! unnoticed.
+
+use std::{fs, io, path::Path};
+
+use clap::ValueEnum;
+use thiserror::Error;
+
+use crate::{JavaVersion, cli::OutputFormat, floor::PackageFloor};
+
+pub const CONFIG_FILE: &str = ".jcfv.toml";
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read {0}")]
+    IO(String, #[source] io::Error),
+    #[error("Invalid config on line {line} of {path}: {message}")]
+    Parse {
+        path: String,
+        line: usize,
+        message: String,
+    },
+}
+
+/// What the config sets, anything that isn't in there is `None`
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    pub max: Option<u16>,
+    pub min: Option<u16>,
+    pub recursive: Option<bool>,
+    pub format: Option<OutputFormat>,
+    /// the `[package-floor]` table, in the order of the file
+    pub package_floors: Vec<PackageFloor>,
+}
+
+const KEYS: &str = "max, min, recursive, format";
+const PACKAGE_FLOOR_TABLE: &str = "[package-floor]";
+
+impl Config {
+    /// Looks for a config in `start` and all of its parents, the closest one wins
+    pub fn find(start: &Path) -> Result<Option<Self>, ConfigError> {
+        let file = start
+            .ancestors()
+            .map(|dir| dir.join(CONFIG_FILE))
+            .find(|file| file.is_file());
+        file.map(|file| Self::load(&file)).transpose()
+    }
+
+    pub fn load(file: &Path) -> Result<Self, ConfigError> {
+        let display = file.display().to_string();
+        let content = fs::read_to_string(file).map_err(|e| ConfigError::IO(display.clone(), e))?;
+        Self::parse(&content).map_err(|(line, message)| ConfigError::Parse {
+            path: display,
+            line,
+            message,
+        })
+    }
+
+    /// Errors come with the line they are on
+    fn parse(content: &str) -> Result<Self, (usize, String)> {
+        let mut config = Self::default();
+        // everything after the table header is part of the table, like in TOML
+        let mut in_package_floors = false;
+        for (index, line) in content.lines().enumerate() {
+            let error = |message: String| (index + 1, message);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                if line.split('#').next().unwrap_or_default().trim() != PACKAGE_FLOOR_TABLE
+                    || in_package_floors
+                {
+                    return Err(error(format!(
+                        "{PACKAGE_FLOOR_TABLE} is the only table (and only once), all other settings go at the top"
+                    )));
+                }
+                in_package_floors = true;
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error(format!("expected `key = value`, got `{line}`")));
+            };
+            let key = key.trim();
+            let value = value_of(value).map_err(error)?;
+            if in_package_floors {
+                // package names have dots in them, which would need quotes in real TOML
+                let package = key.trim_matches('"');
+                let floor =
+                    PackageFloor::parse(&format!("{package}:{}", value.as_str())).map_err(error)?;
+                if config
+                    .package_floors
+                    .iter()
+                    .any(|other| other.package() == floor.package())
+                {
+                    return Err(error(format!("`{package}` is set more than once")));
+                }
+                config.package_floors.push(floor);
+                continue;
+            }
+            let duplicate = match key {
+                "max" => config.max.replace(version(value).map_err(error)?).is_some(),
+                "min" => config.min.replace(version(value).map_err(error)?).is_some(),
+                "recursive" => {
+                    let recursive = match value {
+                        Value::Bare("true") => true,
+                        Value::Bare("false") => false,
+                        _ => return Err(error("recursive has to be true or false".to_owned())),
+                    };
+                    config.recursive.replace(recursive).is_some()
+                }
+                "format" => {
+                    let format = OutputFormat::from_str(value.as_str(), false).map_err(error)?;
+                    config.format.replace(format).is_some()
+                }
+                _ => {
+                    return Err(error(format!(
+                        "unknown key `{key}`, expected one of {KEYS}"
+                    )));
+                }
+            };
+            if duplicate {
+                return Err(error(format!("`{key}` is set more than once")));
+            }
+        }
+        Ok(config)
+    }
+}
+
+enum Value<'a> {
+    /// was in quotes
+    String(&'a str),
+    /// numbers and booleans
+    Bare(&'a str),
+}
+
+impl<'a> Value<'a> {
+    fn as_str(&self) -> &'a str {
+        match self {
+            Self::String(value) | Self::Bare(value) => value,
+        }
+    }
+}
+
+/// The value of a line, without a comment that might come after it
+fn value_of(value: &str) -> Result<Value<'_>, String> {
+    let value = value.trim();
+    let value = match value.strip_prefix('"') {
+        Some(quoted) => {
+            let end = quoted
+                .find('"')
+                .ok_or("the string is missing its closing `\"`")?;
+            let rest = quoted[end + 1..].trim();
+            if !rest.is_empty() && !rest.starts_with('#') {
+                return Err(format!("unexpected `{rest}` after the value"));
+            }
+            Value::String(&quoted[..end])
+        }
+        None => Value::Bare(value.split('#').next().unwrap_or_default().trim()),
+    };
+    if value.as_str().is_empty() {
+        return Err("the value is missing".to_owned());
+    }
+    Ok(value)
+}
+
+/// Java versions go either way, `17`, `"17"` or `"1.8"`
+fn version(value: Value) -> Result<u16, String> {
+    value
+        .as_str()
+        .parse::<JavaVersion>()
+        .map(|version| *version)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_parse() {
+        let config = Config::parse(
+            "# pinned for the whole repo\nmax = 17 # LTS\nmin = \"1.8\"\n\nrecursive = true\nformat = \"json\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                max: Some(17),
+                min: Some(8),
+                recursive: Some(true),
+                format: Some(OutputFormat::Json),
+                package_floors: Vec::new(),
+            }
+        );
+        let config = Config::parse(
+            "max = 21
+
+[package-floor]
+\"com.acme.api\" = 17 # public
+com.acme.legacy = \"1.8\"
+",
+        )
+        .unwrap();
+        assert_eq!(config.max, Some(21));
+        assert_eq!(
+            config.package_floors,
+            [
+                PackageFloor::parse("com.acme.api:17").unwrap(),
+                PackageFloor::parse("com.acme.legacy:8").unwrap()
+            ]
+        );
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+
+        let line = |content: &str| Config::parse(content).unwrap_err().0;
+        assert_eq!(line("max = 17\nmax = 21"), 2);
+        assert_eq!(line("[versions]\nmax = 17"), 1);
+        assert_eq!(line("maximum = 17"), 1);
+        assert_eq!(line("\nrecursive = yes"), 2);
+        assert_eq!(line("format = \"xml\""), 1);
+        assert_eq!(line("max = \"17"), 1);
+        assert_eq!(line("max"), 1);
+        assert_eq!(line("max = 52"), 1);
+        assert_eq!(line("[package-floor]\ncom.acme = 17\n\"com/acme\" = 21"), 3);
+        assert_eq!(line("[package-floor]\ncom.acme = new"), 2);
+        assert_eq!(line("[package-floor]\n[package-floor]"), 2);
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    hash::{DefaultHasher, Hasher},
+    io::{self, Read},
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use tracing::debug;
+
+/// Size and hash of a file, which is what files are considered identical by
+type ContentKey = (u64, u64);
+
+fn content_key(file: &str) -> io::Result<ContentKey> {
+    let mut file = File::open(file)?;
+    // not meant to withstand anybody crafting collisions, but the size being part of the key makes accidents even less likely
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.write(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok((size, hasher.finish()))
+}
+
+/// Remembers results by file content, so identical files are only scanned once. Can be shared between threads, only
+/// files with the same content wait for each other
+#[derive(Debug)]
+pub struct Dedupe<T> {
+    /// empty while the first file with that content is being scanned, or if scanning it failed
+    seen: Mutex<HashMap<ContentKey, Arc<Mutex<Option<T>>>>>,
+    /// number of files that didn't need to be scanned
+    duplicates: AtomicUsize,
+}
+
+impl<T: Clone> Dedupe<T> {
+    pub fn new() -> Self {
+        Self {
+            seen: Mutex::new(HashMap::new()),
+            duplicates: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn duplicates(&self) -> usize {
+        self.duplicates.load(Ordering::Relaxed)
+    }
+
+    /// Calls `scan` unless a file with the same content was scanned before, in which case its result is returned.
+    /// Failures aren't remembered
+    pub fn scan<E: From<io::Error>>(
+        &self,
+        file: &str,
+        scan: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        let key = content_key(file)?;
+        let entry = self
+            .seen
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(key)
+            .or_default()
+            .clone();
+        let mut entry = entry.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(result) = &*entry {
+            debug!("{file} has the same content as a file scanned before, not scanning it again");
+            self.duplicates.fetch_add(1, Ordering::Relaxed);
+            return Ok(result.clone());
+        }
+        let result = scan()?;
+        *entry = Some(result.clone());
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{TempDir, jar_with};
+    use std::{fs, thread, time::Duration};
+
+    #[test]
+    fn test_scan_identical_jars() {
+        let dir = TempDir::new("dedupe");
+        let jar = jar_with(&[("Foo.class", &[202, 254, 186, 190, 0, 0, 0, 61])]);
+        let other = jar_with(&[("Foo.class", &[202, 254, 186, 190, 0, 0, 0, 52])]);
+        let paths = ["a.jar", "b.jar", "c.jar"].map(|name| dir.join(name));
+        fs::write(&paths[0], &jar).unwrap();
+        fs::write(&paths[1], &jar).unwrap();
+        fs::write(&paths[2], &other).unwrap();
+
+        let dedupe = Dedupe::new();
+        let mut scanned = Vec::new();
+        for path in &paths {
+            let path = path.to_str().unwrap();
+            let result = dedupe.scan(path, || {
+                scanned.push(path.to_owned());
+                Ok::<_, io::Error>(fs::read(path)?.len())
+            });
+            assert!(result.is_ok());
+        }
+        assert_eq!(dedupe.duplicates(), 1);
+        assert_eq!(scanned.len(), 2);
+        assert!(!scanned.iter().any(|path| path.ends_with("b.jar")));
+    }
+
+    #[test]
+    fn test_scan_identical_jars_in_parallel() {
+        let dir = TempDir::new("dedupe-jobs");
+        let jar = jar_with(&[("Foo.class", &[202, 254, 186, 190, 0, 0, 0, 61])]);
+        let other = jar_with(&[("Foo.class", &[202, 254, 186, 190, 0, 0, 0, 52])]);
+        let paths: Vec<_> = (0..16)
+            .map(|i| {
+                let path = dir.join(format!("{i}.jar"));
+                fs::write(&path, if i % 2 == 0 { &jar } else { &other }).unwrap();
+                path.to_str().unwrap().to_owned()
+            })
+            .collect();
+
+        let dedupe = Dedupe::new();
+        let scanned = AtomicUsize::new(0);
+        let scan = |path: &String| {
+            dedupe.scan(path, || {
+                scanned.fetch_add(1, Ordering::Relaxed);
+                // long enough for the other threads to run into the same content while this one is busy
+                thread::sleep(Duration::from_millis(10));
+                Ok::<_, io::Error>(fs::read(path)?.len())
+            })
+        };
+        let results: Vec<_> = thread::scope(|scope| {
+            crate::parallel::map_in_order(scope, &paths, 4, &scan)
+                .map(Result::unwrap)
+                .collect()
+        });
+        assert_eq!(results.len(), paths.len());
+        assert_eq!(scanned.load(Ordering::Relaxed), 2);
+        assert_eq!(dedupe.duplicates(), 14);
+    }
+}
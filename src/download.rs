@@ -0,0 +1,182 @@
+//! Inputs that are `http://` or `https://` URLs, downloaded into memory instead of being saved anywhere.
+
+use std::{
+    io::Cursor,
+    sync::{Condvar, Mutex, PoisonError},
+    time::Duration,
+};
+
+use thiserror::Error;
+use tracing::{debug, trace};
+use ureq::{Agent, http::StatusCode};
+
+#[derive(Error, Debug)]
+pub enum DownloadError {
+    #[error("Downloading {0} failed")]
+    Request(String, #[source] Box<ureq::Error>),
+    #[error("Downloading {url} failed with status {status}")]
+    Status { url: String, status: StatusCode },
+    #[error("Downloading {0} took longer than {1:?}")]
+    TimedOut(String, Duration),
+}
+
+/// The downloads of the whole process, for [`ScanOptions::max_concurrent_downloads`](crate::ScanOptions)
+pub static DOWNLOADS: Semaphore = Semaphore::new();
+
+/// Keeps count of how many of something are running, to wait for a turn once there are too many
+#[derive(Debug)]
+pub struct Semaphore {
+    running: Mutex<usize>,
+    finished: Condvar,
+}
+
+/// A turn of a [`Semaphore`], over once it is dropped
+pub struct Permit<'a>(&'a Semaphore);
+
+impl Semaphore {
+    pub const fn new() -> Self {
+        Self {
+            running: Mutex::new(0),
+            finished: Condvar::new(),
+        }
+    }
+
+    /// Waits until fewer than `limit` are running. The limit is up to every caller, so they better agree on it
+    pub fn acquire(&self, limit: usize) -> Permit<'_> {
+        let running = self.running.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut running = self
+            .finished
+            .wait_while(running, |running| *running >= limit)
+            .unwrap_or_else(PoisonError::into_inner);
+        *running += 1;
+        Permit(self)
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *self
+            .0
+            .running
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) -= 1;
+        self.0.finished.notify_one();
+    }
+}
+
+/// Whether the input is something to download rather than a path
+pub fn is_url(input: &str) -> bool {
+    ["http://", "https://"].iter().any(|scheme| {
+        input
+            .get(..scheme.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(scheme))
+    })
+}
+
+/// All of the response, in memory as zips want to seek. Redirects are followed, anything but a 200 in the end is an
+/// error
+pub fn fetch(url: &str, timeout: Option<Duration>) -> Result<Cursor<Vec<u8>>, DownloadError> {
+    let agent: Agent = Agent::config_builder()
+        .timeout_global(timeout)
+        .http_status_as_error(false)
+        .build()
+        .into();
+    let failed = |e: ureq::Error| match (e, timeout) {
+        (ureq::Error::Timeout(_), Some(timeout)) => {
+            DownloadError::TimedOut(url.to_owned(), timeout)
+        }
+        (e, _) => DownloadError::Request(url.to_owned(), Box::new(e)),
+    };
+    trace!("Downloading {url}");
+    let mut response = agent.get(url).call().map_err(failed)?;
+    if response.status() != StatusCode::OK {
+        return Err(DownloadError::Status {
+            url: url.to_owned(),
+            status: response.status(),
+        });
+    }
+    // archives can be a lot larger than the default limit, which is meant for API responses
+    let body = response
+        .body_mut()
+        .with_config()
+        .limit(u64::MAX)
+        .read_to_vec()
+        .map_err(failed)?;
+    debug!("Downloaded {} bytes from {url}", body.len());
+    Ok(Cursor::new(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+    };
+
+    #[test]
+    fn test_semaphore_caps_downloads() {
+        let semaphore = Semaphore::new();
+        let (running, most) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let _permit = semaphore.acquire(2);
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    most.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(20));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+        assert_eq!(*semaphore.running.lock().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("https://repo1.maven.org/maven2/foo.jar"));
+        assert!(is_url("HTTP://localhost:8080/app.jar"));
+        assert!(!is_url("ftp://example.com/app.jar"));
+        assert!(!is_url("https.jar"));
+        assert!(!is_url("target/app.jar"));
+    }
+
+    /// A server that answers a single request with `response`, or doesn't answer at all without one
+    fn serve_once(response: Option<&'static [u8]>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/app.jar", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            // the request is small enough to come in one go
+            let _ = stream.read(&mut [0; 4096]);
+            match response {
+                Some(response) => stream.write_all(response).unwrap(),
+                None => thread::sleep(Duration::from_secs(5)),
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_fetch() {
+        let url = serve_once(Some(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nPK\x03\x04",
+        ));
+        assert_eq!(fetch(&url, None).unwrap().into_inner(), b"PK\x03\x04");
+
+        let url = serve_once(Some(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n"));
+        assert!(matches!(
+            fetch(&url, None),
+            Err(DownloadError::Status { status, .. }) if status == StatusCode::NOT_FOUND
+        ));
+
+        let url = serve_once(None);
+        assert!(matches!(
+            fetch(&url, Some(Duration::from_millis(100))),
+            Err(DownloadError::TimedOut(_, _))
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+//! Exit codes by what went wrong, so that scripts can tell a missing file from a class that is too new.
+
+use std::io;
+
+use thiserror::Error;
+use zip::result::ZipError;
+
+use crate::{
+    EmptyInput, ExtractedJarError, JavaClassError, NotStrict, UnknownFormat,
+    classfile::ClassFileError, download::DownloadError, floor::FloorError, policy::PolicyError,
+    tar::TarError, verify::VerifyError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    /// anything that doesn't fit any of the others
+    Failure = 1,
+    // 2 is what clap exits with for invalid arguments
+    /// a file couldn't be read
+    Io = 3,
+    /// something is above `--max`, below `--min` or failed any of the other checks about versions
+    Version = 4,
+    /// a file isn't a class or an archive, or is broken
+    Format = 5,
+}
+
+/// For the end of `--help`
+pub const EXIT_CODES_HELP: &str = "Exit codes:
+  0  everything is fine
+  1  any other error
+  2  invalid arguments
+  3  a file couldn't be read (or downloaded)
+  4  a version check failed (--max, --min, verify, --policy, --package-floor, preview classes, ...)
+  5  a file isn't a class file or a supported archive, or is broken";
+
+/// What the run failed for in the end, with the exit code that fits best
+#[derive(Error, Debug)]
+#[error("{message}")]
+pub struct Failed {
+    pub message: String,
+    pub code: ExitCode,
+}
+
+impl ExitCode {
+    /// Goes by the first error in the chain that says what kind of problem this is. Errors about the format only
+    /// count if there is nothing more specific behind them, they are often just wrapping an I/O error
+    pub fn of(error: &anyhow::Error) -> Self {
+        let mut code = Self::Failure;
+        for cause in error.chain() {
+            if let Some(failed) = cause.downcast_ref::<Failed>() {
+                return failed.code;
+            }
+            if is_version_check(cause) {
+                return Self::Version;
+            }
+            // whatever went wrong, the file couldn't be had
+            if cause.is::<DownloadError>() {
+                return Self::Io;
+            }
+            if let Some(error) = cause.downcast_ref::<io::Error>() {
+                return match error.kind() {
+                    // that's what reading broken data tends to look like
+                    io::ErrorKind::UnexpectedEof | io::ErrorKind::InvalidData => Self::Format,
+                    _ => Self::Io,
+                };
+            }
+            if cause.is::<ExtractedJarError>()
+                || cause.is::<JavaClassError>()
+                || cause.is::<ClassFileError>()
+                || cause.is::<ZipError>()
+                || cause.is::<TarError>()
+                || cause.is::<sevenz_rust::Error>()
+                || cause.is::<UnknownFormat>()
+                || cause.is::<NotStrict>()
+                || cause.is::<EmptyInput>()
+            {
+                code = Self::Format;
+            }
+        }
+        code
+    }
+}
+
+fn is_version_check(cause: &(dyn std::error::Error + 'static)) -> bool {
+    cause.is::<PolicyError>()
+        || cause.is::<VerifyError>()
+        || cause.is::<FloorError>()
+        || matches!(
+            cause.downcast_ref::<ExtractedJarError>(),
+            Some(
+                ExtractedJarError::NotUniform { .. }
+                    | ExtractedJarError::BelowPackageFloor { .. }
+                    | ExtractedJarError::Preview(_)
+            )
+        )
+        || matches!(
+            cause.downcast_ref::<JavaClassError>(),
+            Some(JavaClassError::Preview(_))
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code_of() {
+        let not_found = io::Error::from(io::ErrorKind::NotFound);
+        let error = anyhow::Error::from(ExtractedJarError::IO(not_found));
+        assert_eq!(ExitCode::of(&error), ExitCode::Io);
+        let error = anyhow::Error::from(ExtractedJarError::NotAJar);
+        assert_eq!(ExitCode::of(&error), ExitCode::Format);
+        let error = anyhow::Error::from(JavaClassError::InsufficientBytes(3));
+        assert_eq!(ExitCode::of(&error), ExitCode::Format);
+        let error = anyhow::Error::from(ExtractedJarError::Preview("Foo.class".to_owned()))
+            .context("Failed to scan app.jar");
+        assert_eq!(ExitCode::of(&error), ExitCode::Version);
+        let error = anyhow::Error::from(Failed {
+            message: "too high".to_owned(),
+            code: ExitCode::Version,
+        });
+        assert_eq!(ExitCode::of(&error), ExitCode::Version);
+        assert_eq!(ExitCode::of(&anyhow::anyhow!("oops")), ExitCode::Failure);
+    }
+}
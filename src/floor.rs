@@ -0,0 +1,151 @@
+//! `--package-floor`: minimum versions for the classes of a package, for codebases where some layers have to be newer than others.
+
+use std::collections::BTreeMap;
+
+use thiserror::Error;
+
+use crate::JavaVersion;
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct PackageFloor {
+    /// with `/` as the separator, like in the entry names
+    package: String,
+    min: u16,
+}
+
+impl PackageFloor {
+    /// Parses `PACKAGE:MIN`, with the package in either `com.acme.api` or `com/acme/api` notation
+    pub fn parse(floor: &str) -> Result<Self, String> {
+        let Some((package, min)) = floor.rsplit_once(':') else {
+            return Err(format!("expected PACKAGE:MIN, got `{floor}`"));
+        };
+        let package = package.replace('.', "/").trim_matches('/').to_owned();
+        if package.is_empty() {
+            return Err(format!("`{floor}` needs a package"));
+        }
+        let min = min.parse::<JavaVersion>().map_err(|e| e.to_string())?;
+        Ok(Self { package, min: *min })
+    }
+
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    /// The floors of the config along with the ones of the flags, which win for the same package
+    pub fn merge(config: Vec<Self>, flags: Vec<Self>) -> Vec<Self> {
+        let mut floors: Vec<_> = config
+            .into_iter()
+            .filter(|floor| !flags.iter().any(|flag| flag.package == floor.package))
+            .collect();
+        floors.extend(flags);
+        floors
+    }
+
+    /// Whether the class is in the package, or in one below it
+    fn contains(&self, class: &str) -> bool {
+        class
+            .strip_prefix(&self.package)
+            .is_some_and(|rest| rest.starts_with('/'))
+    }
+}
+
+/// Classes that are too old, by package along with the floor of that package
+type Violations = BTreeMap<String, (u16, Vec<(String, JavaVersion)>)>;
+
+#[derive(Error, Debug, PartialEq)]
+#[error("classes below the floor of their package:{}", list(.0))]
+pub struct FloorError(Violations);
+
+fn list(violations: &Violations) -> String {
+    let mut out = String::new();
+    for (package, (min, classes)) in violations {
+        out.push_str(&format!("\n  {package} ({} or newer):", JavaVersion(*min)));
+        for (class, version) in classes {
+            out.push_str(&format!("\n    {class}: {version}"));
+        }
+    }
+    out
+}
+
+/// Checks every class against the most specific floor that applies to it, with violations grouped by package.
+/// Classes of nested jars go by their name inside of that jar
+pub fn check<'a>(
+    floors: &[PackageFloor],
+    classes: impl IntoIterator<Item = (&'a str, JavaVersion)>,
+) -> Result<(), FloorError> {
+    let mut violations = Violations::new();
+    for (name, version) in classes {
+        let class = name.rsplit("!/").next().unwrap_or(name);
+        let floor = floors
+            .iter()
+            .filter(|floor| floor.contains(class))
+            .max_by_key(|floor| floor.package.len());
+        if let Some(floor) = floor
+            && *version < floor.min
+        {
+            violations
+                .entry(floor.package.clone())
+                .or_insert_with(|| (floor.min, Vec::new()))
+                .1
+                .push((name.to_owned(), version));
+        }
+    }
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(FloorError(violations))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_floor_parse() {
+        let floor = PackageFloor::parse("com.acme.api:17").unwrap();
+        assert_eq!(floor, PackageFloor::parse("com/acme/api/:17").unwrap());
+        assert!(floor.contains("com/acme/api/Foo.class"));
+        assert!(floor.contains("com/acme/api/v2/Foo.class"));
+        assert!(!floor.contains("com/acme/apix/Foo.class"));
+        assert!(PackageFloor::parse("com.acme").is_err());
+        assert!(PackageFloor::parse(":17").is_err());
+    }
+
+    #[test]
+    fn test_package_floor_merge() {
+        let floor = |floor| PackageFloor::parse(floor).unwrap();
+        assert_eq!(
+            PackageFloor::merge(
+                vec![floor("com.acme:8"), floor("com.acme.api:11")],
+                vec![floor("com/acme/api:17"), floor("org.other:21")]
+            ),
+            [
+                floor("com.acme:8"),
+                floor("com.acme.api:17"),
+                floor("org.other:21")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        let floors = [
+            PackageFloor::parse("com.acme:8").unwrap(),
+            PackageFloor::parse("com.acme.api:17").unwrap(),
+        ];
+        let classes = [
+            ("com/acme/api/Api.class", JavaVersion(17)),
+            ("com/acme/legacy/Old.class", JavaVersion(8)),
+            ("com/acme/api/Stale.class", JavaVersion(11)),
+            ("lib/x.jar!/com/acme/api/Nested.class", JavaVersion(8)),
+            ("org/other/Ancient.class", JavaVersion(1)),
+        ];
+        assert_eq!(check(&floors, classes[..2].iter().cloned()), Ok(()));
+        let error = check(&floors, classes.iter().cloned()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "classes below the floor of their package:\n  com/acme/api (Java 17 or newer):\n    com/acme/api/Stale.class: Java 11\n    lib/x.jar!/com/acme/api/Nested.class: Java 1.8"
+        );
+    }
+}
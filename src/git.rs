@@ -0,0 +1,140 @@
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use thiserror::Error;
+use tracing::{debug, trace};
+
+#[derive(Error, Debug)]
+pub enum GitError {
+    #[error("Failed to run git. Is it installed?")]
+    IO(#[from] io::Error),
+    #[error("{0} is not inside of a git repository")]
+    NotARepository(String),
+    #[error("git {command} failed: {stderr}")]
+    Failed { command: String, stderr: String },
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, GitError> {
+    trace!("Running git {args:?} in {dir:?}");
+    let output = Command::new("git").args(args).current_dir(dir).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+        if stderr.contains("not a git repository") {
+            return Err(GitError::NotARepository(dir.display().to_string()));
+        }
+        return Err(GitError::Failed {
+            command: args.join(" "),
+            stderr,
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Canonical paths of all files that `git diff --name-only <base>` reports for the repository `dir` is in, plus the
+/// untracked ones that aren't ignored, which git diff doesn't know about.
+///
+/// Files that don't exist anymore (deleted ones) are left out, there is nothing to scan there anyway.
+pub fn changed_files(dir: &Path, base: &str) -> Result<HashSet<PathBuf>, GitError> {
+    let root = git(dir, &["rev-parse", "--show-toplevel"])?;
+    let root = PathBuf::from(root.trim());
+    // `--` makes sure the base is taken as a revision, even if a file with the same name exists
+    let changed = git(&root, &["diff", "--name-only", base, "--"])?;
+    let untracked = git(&root, &["ls-files", "--others", "--exclude-standard"])?;
+
+    let changed: HashSet<_> = changed
+        .lines()
+        .chain(untracked.lines())
+        .filter_map(|path| root.join(path).canonicalize().ok())
+        .collect();
+    debug!("git reports {} changed files since {base}", changed.len());
+    Ok(changed)
+}
+
+/// Whether the input is one of the changed files, or a directory containing at least one of them
+pub fn is_changed(input: &str, changed: &HashSet<PathBuf>) -> bool {
+    let Ok(input) = Path::new(input).canonicalize() else {
+        return false;
+    };
+    changed.contains(&input) || changed.iter().any(|path| path.starts_with(&input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TempDir;
+    use std::fs;
+
+    #[test]
+    fn test_changed_files_outside_of_repository() {
+        let dir = TempDir::new("no-git");
+        let result = Command::new("git")
+            .args(["rev-parse", "--show-toplevel"])
+            .current_dir(&dir)
+            .output();
+        match result {
+            // the temp dir is somehow inside of a repository after all, nothing to test here
+            Ok(output) if output.status.success() => return,
+            Err(_) => panic!("git needs to be installed for this test"),
+            Ok(_) => {}
+        }
+        assert!(matches!(
+            changed_files(&dir, "HEAD"),
+            Err(GitError::NotARepository(_))
+        ));
+    }
+
+    #[test]
+    fn test_changed_files() {
+        let dir = TempDir::new("git");
+        let run = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(args)
+                .current_dir(&dir)
+                .env("GIT_AUTHOR_NAME", "test")
+                .env("GIT_AUTHOR_EMAIL", "test@example.com")
+                .env("GIT_COMMITTER_NAME", "test")
+                .env("GIT_COMMITTER_EMAIL", "test@example.com")
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        run(&["init", "-q"]);
+        fs::write(dir.join("Same.class"), b"same").unwrap();
+        fs::write(dir.join("Changed.class"), b"before").unwrap();
+        run(&["add", "."]);
+        run(&[
+            "-c",
+            "commit.gpgsign=false",
+            "commit",
+            "-q",
+            "-m",
+            "initial",
+        ]);
+        fs::write(dir.join("Changed.class"), b"after").unwrap();
+        fs::create_dir(dir.join("lib")).unwrap();
+        fs::write(dir.join("lib/new.jar"), b"untracked").unwrap();
+        fs::write(dir.join("lib/ignored.jar"), b"untracked, but ignored").unwrap();
+        fs::write(dir.join(".gitignore"), b"ignored.jar\n").unwrap();
+
+        let changed = changed_files(&dir, "HEAD").unwrap();
+        let changed_class = dir.join("Changed.class");
+        assert!(is_changed(changed_class.to_str().unwrap(), &changed));
+        assert!(!is_changed(
+            dir.join("Same.class").to_str().unwrap(),
+            &changed
+        ));
+        assert!(is_changed(dir.to_str().unwrap(), &changed));
+        assert!(is_changed(
+            dir.join("lib/new.jar").to_str().unwrap(),
+            &changed
+        ));
+        assert!(!is_changed(
+            dir.join("lib/ignored.jar").to_str().unwrap(),
+            &changed
+        ));
+    }
+}
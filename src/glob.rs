@@ -0,0 +1,162 @@
+//! Shell style glob patterns, translated into a [`Regex`].
+//!
+//! `*` matches anything but a `/`, `**` matches anything including `/` (so `a/**/b` matches `a/b` as well as `a/x/y/b`),
+//! `?` matches a single character that isn't a `/` and `[...]`/`[!...]` match character classes.
+//! A pattern without any `/` only looks at the last component of a path, so `*.jar` matches `lib/foo.jar`.
+
+use std::{
+    fmt::Display,
+    hash::{Hash, Hasher},
+};
+
+use thiserror::Error;
+
+use regex::Regex;
+
+#[derive(Error, Debug, Clone)]
+#[error("Invalid glob {pattern:?}")]
+pub struct GlobError {
+    pattern: String,
+    source: regex::Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Glob {
+    pattern: String,
+    regex: Regex,
+    file_name_only: bool,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Self, GlobError> {
+        Self::build(pattern, !pattern.contains('/'))
+    }
+
+    /// Matches against the whole path, even if the pattern has no `/` in it
+    pub fn anchored(pattern: &str) -> Result<Self, GlobError> {
+        Self::build(pattern, false)
+    }
+
+    fn build(pattern: &str, file_name_only: bool) -> Result<Self, GlobError> {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut regex = String::from("^");
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    i += 1;
+                    if chars.get(i + 1) == Some(&'/') {
+                        // `**/` can also match nothing at all
+                        i += 1;
+                        regex.push_str("(?:.*/)?");
+                    } else {
+                        regex.push_str(".*");
+                    }
+                }
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                '[' => {
+                    regex.push('[');
+                    if matches!(chars.get(i + 1), Some('!' | '^')) {
+                        i += 1;
+                        regex.push('^');
+                    }
+                    // everything up to the closing bracket is taken as is, the regex parser complains if it is broken
+                    while let Some(&c) = chars.get(i + 1) {
+                        i += 1;
+                        if c == '\\' {
+                            regex.push('\\');
+                        }
+                        regex.push(c);
+                        if c == ']' {
+                            break;
+                        }
+                    }
+                }
+                c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+            }
+            i += 1;
+        }
+        regex.push('$');
+
+        Ok(Self {
+            pattern: pattern.to_owned(),
+            regex: Regex::new(&regex).map_err(|source| GlobError {
+                pattern: pattern.to_owned(),
+                source,
+            })?,
+            file_name_only,
+        })
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        let path = path.replace('\\', "/");
+        let path = path.strip_prefix("./").unwrap_or(&path);
+        if self.file_name_only {
+            let file_name = path
+                .trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .unwrap_or(path);
+            self.regex.is_match(file_name)
+        } else {
+            self.regex.is_match(path)
+        }
+    }
+}
+
+// the regex follows from these two
+impl Hash for Glob {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.pattern.hash(state);
+        self.file_name_only.hash(state);
+    }
+}
+
+impl Display for Glob {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        Glob::new(pattern).unwrap().is_match(path)
+    }
+
+    #[test]
+    fn test_glob_file_name_only() {
+        assert!(matches("*.jar", "foo.jar"));
+        assert!(matches("*.jar", "target/lib/foo.jar"));
+        assert!(!matches("*.jar", "foo.jar.bak"));
+        assert!(matches("foo-?.jar", "foo-1.jar"));
+        assert!(matches("foo-[0-9].jar", "lib/foo-1.jar"));
+        assert!(!matches("foo-[!0-9].jar", "lib/foo-1.jar"));
+    }
+
+    #[test]
+    fn test_glob_paths() {
+        assert!(matches("target/*.jar", "target/foo.jar"));
+        assert!(matches("target/*.jar", "./target/foo.jar"));
+        assert!(!matches("target/*.jar", "target/lib/foo.jar"));
+        assert!(matches("target/**/*.jar", "target/foo.jar"));
+        assert!(matches("target/**/*.jar", "target/lib/deep/foo.jar"));
+        assert!(matches("**/test/**", "src/test/Foo.class"));
+        assert!(matches(
+            "com/example/test/**",
+            "com/example/test/FooTest.class"
+        ));
+        assert!(!matches("com/example/test/**", "com/example/Foo.class"));
+        // regex characters are taken literally
+        assert!(matches("a+b(1).jar", "a+b(1).jar"));
+        assert!(!matches("a.jar", "abjar"));
+    }
+
+    #[test]
+    fn test_glob_invalid() {
+        assert!(Glob::new("foo[").is_err());
+    }
+}
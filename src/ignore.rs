@@ -0,0 +1,185 @@
+//! `.classversionignore` files, which exclude inputs with gitignore style patterns.
+//!
+//! The usual rules apply: blank lines and lines starting with `#` don't do anything, `!` re-includes what an earlier
+//! pattern excluded, a trailing `/` only matches directories and a pattern with a `/` anywhere but at the end is relative
+//! to the directory of the file. Anything else matches at any depth. The last pattern that matches wins, but nothing
+//! inside of an excluded directory can be re-included.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+use tracing::debug;
+
+use crate::glob::{Glob, GlobError};
+
+pub const IGNORE_FILE: &str = ".classversionignore";
+
+#[derive(Error, Debug)]
+pub enum IgnoreError {
+    #[error("Failed to read {0}")]
+    IO(String, #[source] io::Error),
+    #[error("Invalid pattern on line {line} of {path}")]
+    Glob {
+        path: String,
+        line: usize,
+        source: GlobError,
+    },
+}
+
+#[derive(Debug)]
+struct Rule {
+    glob: Glob,
+    negated: bool,
+    dir_only: bool,
+}
+
+#[derive(Debug)]
+pub struct IgnoreFile {
+    /// patterns are relative to this
+    base: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl IgnoreFile {
+    /// Looks for an ignore file in `start` and all of its parents, the closest one wins
+    pub fn find(start: &Path) -> Result<Option<Self>, IgnoreError> {
+        let Some(file) = start
+            .ancestors()
+            .map(|dir| dir.join(IGNORE_FILE))
+            .find(|file| file.is_file())
+        else {
+            return Ok(None);
+        };
+        let path = file.display().to_string();
+        debug!("Using {path}");
+        let content = fs::read_to_string(&file).map_err(|e| IgnoreError::IO(path.clone(), e))?;
+        let base = file
+            .parent()
+            .unwrap_or(Path::new("."))
+            .canonicalize()
+            .map_err(|e| IgnoreError::IO(path.clone(), e))?;
+        Self::parse(base, &content).map(Some).map_err(|e| match e {
+            IgnoreError::Glob { line, source, .. } => IgnoreError::Glob { path, line, source },
+            e => e,
+        })
+    }
+
+    pub fn parse(base: PathBuf, content: &str) -> Result<Self, IgnoreError> {
+        let mut rules = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, pattern) = match line.strip_prefix('!') {
+                Some(pattern) => (true, pattern),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, pattern) = match pattern.strip_suffix('/') {
+                Some(pattern) => (true, pattern),
+                None => (false, pattern),
+            };
+            // a slash anywhere makes the pattern relative to the ignore file, without one it matches at any depth
+            let glob = if pattern.contains('/') {
+                Glob::anchored(pattern.trim_start_matches('/'))
+            } else {
+                Glob::new(pattern)
+            }
+            .map_err(|source| IgnoreError::Glob {
+                path: IGNORE_FILE.to_owned(),
+                line: index + 1,
+                source,
+            })?;
+            rules.push(Rule {
+                glob,
+                negated,
+                dir_only,
+            });
+        }
+        Ok(Self { base, rules })
+    }
+
+    /// Whether the input should be skipped. Inputs outside of the directory of the ignore file never are
+    pub fn is_ignored(&self, input: &str) -> bool {
+        let Ok(path) = Path::new(input).canonicalize() else {
+            return false;
+        };
+        let Ok(relative) = path.strip_prefix(&self.base) else {
+            return false;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        self.is_ignored_relative(&relative, path.is_dir())
+    }
+
+    /// Same as [`IgnoreFile::is_ignored`], for a path that is already relative to the ignore file
+    fn is_ignored_relative(&self, relative: &str, is_dir: bool) -> bool {
+        // an excluded directory excludes everything in it, no matter what comes later
+        let parents = relative.match_indices('/').map(|(end, _)| &relative[..end]);
+        if parents
+            .into_iter()
+            .any(|parent| self.last_match(parent, true))
+        {
+            return true;
+        }
+        self.last_match(relative, is_dir)
+    }
+
+    fn last_match(&self, path: &str, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.glob.is_match(path))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TempDir;
+
+    fn ignore_file(content: &str) -> IgnoreFile {
+        IgnoreFile::parse(PathBuf::from("/repo"), content).unwrap()
+    }
+
+    #[test]
+    fn test_ignore_file_patterns() {
+        let ignore = ignore_file(
+            "# comment\n\n*-sources.jar\nbuild/\n/fixtures/*.jar\n!fixtures/keep.jar\nold/\n!old/keep.jar\n",
+        );
+        assert!(ignore.is_ignored_relative("foo-sources.jar", false));
+        assert!(ignore.is_ignored_relative("lib/foo-sources.jar", false));
+        assert!(!ignore.is_ignored_relative("lib/foo.jar", false));
+        // directories only
+        assert!(ignore.is_ignored_relative("build/libs/app.jar", false));
+        assert!(ignore.is_ignored_relative("sub/build/app.jar", false));
+        assert!(!ignore.is_ignored_relative("build", false));
+        // anchored
+        assert!(ignore.is_ignored_relative("fixtures/broken.jar", false));
+        assert!(!ignore.is_ignored_relative("sub/fixtures/broken.jar", false));
+        // negation, but not out of an excluded directory
+        assert!(!ignore.is_ignored_relative("fixtures/keep.jar", false));
+        assert!(ignore.is_ignored_relative("old/keep.jar", false));
+    }
+
+    #[test]
+    fn test_ignore_file_invalid() {
+        let result = IgnoreFile::parse(PathBuf::from("/repo"), "ok.jar\nbroken[\n");
+        assert!(matches!(result, Err(IgnoreError::Glob { line: 2, .. })));
+    }
+
+    #[test]
+    fn test_ignore_file_find() {
+        let dir = TempDir::new("ignore");
+        let nested = dir.join("a/b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(dir.join(IGNORE_FILE), "*.jar\n").unwrap();
+        fs::write(nested.join("app.jar"), b"").unwrap();
+
+        let ignore = IgnoreFile::find(&nested).unwrap().unwrap();
+        assert!(ignore.is_ignored(nested.join("app.jar").to_str().unwrap()));
+    }
+}
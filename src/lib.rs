@@ -0,0 +1,3483 @@
+//! Finds out which Java version class files, jars and other archives need, for anyone who'd rather call this from
+//! their own tool than run the binary.
+//!
+//! [`process_class`] and [`process_archive`] are the entry points. What they log are `tracing` events, so nothing
+//! shows up without a subscriber. [`logging::init`] sets up the one the binary uses.
+
+mod archive;
+mod cache;
+mod classfile;
+mod cli;
+mod config;
+mod dedupe;
+mod download;
+pub mod exit;
+mod floor;
+mod git;
+mod glob;
+mod ignore;
+pub mod logging;
+mod manifest;
+mod merge;
+mod parallel;
+mod policy;
+mod progress;
+mod release;
+mod report;
+mod runtime;
+mod sevenz;
+mod tar;
+mod verify;
+mod walk;
+mod watch;
+
+use anyhow::{Context, bail};
+use archive::{
+    ArchiveReader, Bundle, Capped, Counted, ZipSlice, archive_from_reader, has_tar_magic,
+    is_bundle, is_tar, open_archive,
+};
+use cache::Cache;
+use classfile::ClassFileError;
+use cli::{Aggregate, Cli, Destination, OutputFormat};
+use dedupe::Dedupe;
+use exit::{ExitCode, Failed};
+use floor::FloorError;
+pub use floor::PackageFloor;
+pub use glob::Glob;
+use ignore::{IGNORE_FILE, IgnoreFile};
+use manifest::{MANIFEST_PATH, Manifest};
+use policy::Policies;
+use progress::Progress;
+pub use regex::Regex;
+pub use release::JavaRelease;
+use report::{
+    FileError, FileReport, StreamingObject, Summary, TextOptions, count_versions, header_fields,
+    pretty, push_errors, render_aggregate_json, render_aggregate_text, render_batch_summary,
+    render_classes_above_json, render_classes_above_text, render_count, render_json,
+    render_prometheus, render_summary, render_text, render_treemap, render_zero_class_jars,
+    sort_report, zero_class_jars_field,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, json};
+use sevenz::MAGIC_7Z_HEADER;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display},
+    fs::{self, File},
+    io::{self, Cursor, IsTerminal, Read, Seek},
+    ops::{ControlFlow, Deref},
+    path::Path,
+    str::FromStr,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+use tar::TarError;
+use thiserror::Error;
+use tracing::{debug, info, trace, warn};
+use zip::result::ZipError;
+
+/// Java version, 8 for Java 1.8 and 17 for Java 17
+#[derive(
+    Debug, PartialEq, PartialOrd, Clone, Eq, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+pub struct JavaVersion(pub u16);
+
+/// What has to be taken off the major version of a class file to get the Java version, 52 is Java 8.
+/// The 44 was scientifically chosen by looking at the table in
+/// https://en.wikipedia.org/wiki/Java_class_file#General_layout and doing second grade math
+/// (might be a different grade, no idea actually)
+pub const JAVA_VERSION_OFFSET: u16 = 44;
+
+impl Deref for JavaVersion {
+    type Target = u16;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl JavaVersion {
+    /// Compares this version against a raw class file major version (e.g. 52 for Java 8),
+    /// so no one has to remember which side of the comparison needs the offset applied.
+    pub fn cmp_class_major(&self, major: u16) -> Ordering {
+        self.class_major().cmp(&u32::from(major))
+    }
+
+    /// The lowest and the highest version of the classes, in one go. Both are `JavaVersion(0)` without any classes,
+    /// just like with [`JavaVersion::from_iter`]
+    pub fn version_range<T: IntoIterator<Item = JavaClass>>(iter: T) -> (JavaVersion, JavaVersion) {
+        iter.into_iter()
+            .map(JavaVersion::from)
+            .fold(None, |range, version| match range {
+                None => Some((version.clone(), version)),
+                Some((min, max)) => Some((min.min(version.clone()), max.max(version))),
+            })
+            .unwrap_or((JavaVersion(0), JavaVersion(0)))
+    }
+
+    /// How far this version is above `max`, if it is above it at all
+    pub fn over_max(&self, max: u16) -> Option<u16> {
+        self.0.checked_sub(max).filter(|&delta| delta > 0)
+    }
+
+    /// The release by name, to `match` on
+    pub fn release(&self) -> JavaRelease {
+        JavaRelease::from(self)
+    }
+
+    /// The major version that class files of this version have, e.g. 52 for Java 8
+    pub fn class_major(&self) -> u32 {
+        // widened, as there is no telling where a version came from
+        u32::from(self.0) + u32::from(JAVA_VERSION_OFFSET)
+    }
+
+    /// What the release is called, `1.x` up to 8 and just the number since 9.
+    /// Major 45 was both 1.0 and 1.1, which comes out as 1.1 here
+    pub fn marketing_name(&self) -> String {
+        match self.0 {
+            0..=8 => format!("1.{}", self.0),
+            version => version.to_string(),
+        }
+    }
+
+    /// Whether this is a release with long term support
+    pub fn is_lts(&self) -> bool {
+        // since 17, there is a new LTS every two years, so every fourth release
+        matches!(self.0, 8 | 11) || (self.0 >= 17 && (self.0 - 17).is_multiple_of(4))
+    }
+
+    /// The first LTS release that is at least this version
+    pub fn next_lts(&self) -> JavaVersion {
+        // anything before 8 can't be LTS as the whole concept came later, 8 runs all of it anyway
+        let mut version = JavaVersion(self.0.max(8));
+        while !version.is_lts() {
+            version.0 += 1;
+        }
+        version
+    }
+
+    /// One line for people who don't want to know about class file versions, for `--recommend-jdk`
+    pub fn jdk_recommendation(&self, lts: bool) -> String {
+        let mut recommendation = format!("These artifacts require JDK {} or newer", self.0);
+        if lts {
+            let next_lts = self.next_lts();
+            if next_lts == *self {
+                recommendation.push_str(", which is an LTS release");
+            } else {
+                recommendation
+                    .push_str(&format!(", the closest LTS release is JDK {}", next_lts.0));
+            }
+        }
+        recommendation
+    }
+
+    /// What to tell someone whose classes are at this version, but have to run on `max`
+    pub fn remediation_hint(&self, max: u16) -> String {
+        if max >= 8 {
+            format!(
+                "compiled for Java {}; rebuild with `--release {max}` or lower",
+                self.0
+            )
+        } else {
+            // current JDKs can't target anything below 8 anymore, and `--release` only exists since 9 anyway
+            format!(
+                "compiled for Java {}; rebuild with `-source {max} -target {max}` on a JDK that still supports it",
+                self.0
+            )
+        }
+    }
+}
+
+impl From<JavaClass> for JavaVersion {
+    fn from(value: JavaClass) -> Self {
+        // anything below the offset isn't a real class, which is what 0 stands for already (like without any classes)
+        Self(value.major.saturating_sub(JAVA_VERSION_OFFSET))
+    }
+}
+
+impl FromIterator<JavaClass> for JavaVersion {
+    fn from_iter<T: IntoIterator<Item = JavaClass>>(iter: T) -> Self {
+        iter.into_iter()
+            .map(|elem| elem.into())
+            .max()
+            .unwrap_or(JavaVersion(0))
+    }
+}
+
+/// Newest release at the time of writing. Anything newer is fine as well, this is only used to come up with suggestions
+const LATEST_KNOWN_VERSION: u16 = 25;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ParseJavaVersionError {
+    #[error("`{0}` is not a Java version, expected something like `17` or `1.8`")]
+    Invalid(String),
+    #[error(
+        "`{input}` is not a Java version, expected something like `17` or `1.8`. Did you mean {suggestion}?"
+    )]
+    Suggestion { input: String, suggestion: u16 },
+    #[error(
+        "`{input}` looks like a class file version, not a Java version. That would be {suggestion}, pass `{}` for it", .suggestion.marketing_name()
+    )]
+    ClassFileVersion {
+        input: String,
+        suggestion: JavaVersion,
+    },
+}
+
+impl FromStr for JavaVersion {
+    type Err = ParseJavaVersionError;
+
+    /// Takes `17` as well as the old notation `1.8`, which only ever went up to 8
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let legacy = s.strip_prefix("1.").filter(|rest| !rest.is_empty());
+        if let Some(version) = legacy.and_then(|rest| rest.parse().ok())
+            && (1..=8).contains(&version)
+        {
+            return Ok(JavaVersion(version));
+        }
+        if let Ok(version) = s.parse::<u16>() {
+            // `52` for Java 8, straight from javap or the error message of an old JVM. Future versions that high are
+            // still years away, so anything in between is taken for one
+            if version > LATEST_KNOWN_VERSION
+                && (MIN_CLASS_MAJOR..=JAVA_VERSION_OFFSET + LATEST_KNOWN_VERSION).contains(&version)
+            {
+                return Err(ParseJavaVersionError::ClassFileVersion {
+                    input: s.to_owned(),
+                    suggestion: JavaVersion(version - JAVA_VERSION_OFFSET),
+                });
+            }
+            return Ok(JavaVersion(version));
+        }
+
+        // whatever number is in there (`jdk17`, `17a`, `1.17`) is the best guess of what was meant
+        let digits: String = legacy
+            .unwrap_or(s)
+            .chars()
+            .skip_while(|c| !c.is_ascii_digit())
+            .take_while(char::is_ascii_digit)
+            .collect();
+        if digits.is_empty() {
+            return Err(ParseJavaVersionError::Invalid(s.to_owned()));
+        }
+        let suggestion = digits
+            .parse::<u16>()
+            .map_or(LATEST_KNOWN_VERSION, |version| {
+                version.clamp(1, LATEST_KNOWN_VERSION)
+            });
+        Err(ParseJavaVersionError::Suggestion {
+            input: s.to_owned(),
+            suggestion,
+        })
+    }
+}
+
+impl Display for JavaVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 0 is what no classes at all come out as, there never was a Java 1.0 major version of its own
+        if self.0 == 0 {
+            return f.write_str("no classes");
+        }
+        write!(f, "Java {}", self.marketing_name())
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Clone, Eq, Hash)]
+pub struct JavaClass {
+    pub major: u16,
+    /// [`PREVIEW_MINOR_VERSION`] for classes that use preview features, 0 for pretty much anything else
+    pub minor: u16,
+}
+
+const MAGIC_CLASS_HEADER: [u8; 4] = [202, 254, 186, 190]; // CAFEBABE
+const MAGIC_ZIP_HEADER: [u8; 4] = [80, 75, 3, 4]; // I don't think this turns into anything fancy
+
+#[derive(Error, Debug)]
+pub enum JavaClassError {
+    #[error("Failed to read bytes from file")]
+    Read(#[from] io::Error),
+    #[error("Should have got at least 8 bytes, got {0}")]
+    InsufficientBytes(usize),
+    #[error("Not a java class")]
+    NotAClassFile,
+    #[error("Failed to read class file")]
+    ClassFile(#[from] ClassFileError),
+    #[error("{0} was compiled with --enable-preview")]
+    Preview(String),
+    #[error(
+        "Major version {0} doesn't belong to any Java version, the class file is probably broken"
+    )]
+    ImplausibleVersion(u16),
+}
+
+/// Minor version of classes that use preview features of their Java version, which only exist since Java 12
+pub const PREVIEW_MINOR_VERSION: u16 = 0xFFFF;
+/// Java 1.0 and 1.1 both had 45, there has never been anything lower
+pub const MIN_CLASS_MAJOR: u16 = 45;
+/// Anything above this is taken for garbage rather than a class from the future. Leaves room for about ten more
+/// years of releases after [`LATEST_KNOWN_VERSION`]
+pub const DEFAULT_MAX_CLASS_MAJOR: u16 = 89;
+
+impl JavaClass {
+    /// Reads the header of a class, with a major version of up to [`DEFAULT_MAX_CLASS_MAJOR`]
+    pub fn new<T: Read>(f: T) -> Result<Self, JavaClassError> {
+        Self::with_max_major(f, DEFAULT_MAX_CLASS_MAJOR)
+    }
+
+    /// Like [`JavaClass::new`], but with a say in which major versions are too high to be real
+    pub fn with_max_major<T: Read>(f: T, max_major: u16) -> Result<Self, JavaClassError> {
+        let mut buffer = Vec::with_capacity(8);
+
+        // a single read might not get everything, pipes and decompressing readers hand out whatever they have.
+        // Like read_exact, but knowing how much there was
+        let read_bytes = f.take(8).read_to_end(&mut buffer)?;
+        if read_bytes != 8 {
+            return Err(JavaClassError::InsufficientBytes(read_bytes));
+        }
+
+        if buffer[..4] != MAGIC_CLASS_HEADER {
+            return Err(JavaClassError::NotAClassFile);
+        }
+
+        let minor = u16::from_be_bytes([buffer[4], buffer[5]]);
+        let major = u16::from_be_bytes([buffer[6], buffer[7]]);
+        // the magic alone is four bytes, that's not much to go by for a truncated or misidentified file
+        if !(MIN_CLASS_MAJOR..=max_major).contains(&major) {
+            return Err(JavaClassError::ImplausibleVersion(major));
+        }
+
+        Ok(JavaClass { major, minor })
+    }
+
+    /// Whether the class was compiled with `--enable-preview`, which means only exactly its Java version can run it
+    pub fn is_preview(&self) -> bool {
+        // before 12 (major 56), the minor version didn't mean anything like that
+        self.minor == PREVIEW_MINOR_VERSION && self.major >= 56
+    }
+
+    /// Reads the whole class instead of just the header, to find out if it has debug info in it as well
+    pub fn with_debug_info<T: Read>(
+        mut f: T,
+        max_major: u16,
+    ) -> Result<(Self, bool), JavaClassError> {
+        let mut bytes = Vec::new();
+        f.read_to_end(&mut bytes)?;
+        let class = Self::with_max_major(bytes.as_slice(), max_major)?;
+        Ok((class, classfile::has_debug_info(&bytes)?))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ExtractedJarError {
+    #[error("I/O Error")]
+    IO(#[from] io::Error),
+    #[error("Failed to read jar as zip file")]
+    Zip(#[from] ZipError),
+    #[error("Failed to read tar archive")]
+    Tar(#[from] TarError),
+    #[error("Failed to read 7z archive")]
+    SevenZ(#[from] sevenz_rust::Error),
+    #[error("Not a jar, tar or 7z file")]
+    NotAJar,
+    #[error("Should have got at least 4 bytes, got {0}")]
+    InsufficientBytes(usize),
+    #[error("Invalid class file")]
+    JavaClass(#[from] JavaClassError),
+    #[error("No suitable class files found. Maybe this isn't actually a Jar?")]
+    NoClassFiles,
+    #[error("Implementation-Version {actual} in the manifest does not match `{expected}`")]
+    ManifestVersionMismatch { expected: String, actual: String },
+    #[error("No Implementation-Version in the manifest, expected something matching `{0}`")]
+    MissingManifestVersion(String),
+    #[error("{jar} contains classes with different versions: {examples}")]
+    NotUniform { jar: String, examples: String },
+    #[error("{0} was compiled with --enable-preview")]
+    Preview(String),
+    #[error("{jar} has {violations}")]
+    BelowPackageFloor { jar: String, violations: FloorError },
+    #[error("{jar} is nested more than {max} jar(s) deep, not going any further")]
+    NestedTooDeep { jar: String, max: usize },
+    /// `entry` is empty if it's about the archive itself
+    #[error("{entry} is over the limit of {limit}, not going any further in case it's a zip bomb")]
+    TooLarge { entry: String, limit: String },
+}
+
+/// Things that change how archives are scanned
+#[derive(Debug, Default, Clone)]
+pub struct ScanOptions {
+    /// pattern the Implementation-Version in the manifest has to match
+    pub manifest_version: Option<Regex>,
+    /// warn if the JDK the manifest says the jar was built with doesn't match the classes
+    pub check_build_jdk: bool,
+    /// stop scanning an archive as soon as a class with a version above this is found.
+    /// Only useful if nobody cares what the actual highest version is
+    pub stop_above: Option<u16>,
+    /// hand out the version of every single class instead of just the highest one
+    pub keep_classes: bool,
+    /// removed from the start of entry names in the output
+    pub strip_entry_prefix: Option<Regex>,
+    /// fail for archives with classes of more than one version
+    pub uniform: bool,
+    /// count the classes that still have debug info in them. Means reading classes completely instead of just the header
+    pub check_stripped: bool,
+    /// fail for the first class compiled with `--enable-preview`, wherever it is. This looks into nested jars and
+    /// multi-release directories of jars as well, but only for the sake of finding preview classes
+    pub fail_on_preview: bool,
+    /// minimum versions of the classes in these packages
+    pub package_floors: Vec<PackageFloor>,
+    /// count the classes in `META-INF/versions/<n>/` of multi-release jars as well
+    pub multi_release: bool,
+    /// count every class in `META-INF`, not just the ones of multi-release jars
+    pub include_meta_inf: bool,
+    /// only the classes whose entry path matches one of these count, all of them if empty
+    pub include: Vec<Glob>,
+    /// the classes whose entry path matches any of these don't count, even if they match `include`
+    pub exclude: Vec<Glob>,
+    /// scan the jars inside of any archive (like `BOOT-INF/lib` of Spring Boot), and the jars inside of those
+    pub nested_jars: bool,
+    /// how many jars deep nested jars are read, [`DEFAULT_MAX_NESTING_DEPTH`] if not set
+    pub max_nesting_depth: Option<usize>,
+    /// the most an entry of an archive may decompress to, [`DEFAULT_MAX_ENTRY_BYTES`] if not set
+    pub max_entry_bytes: Option<u64>,
+    /// the most entries an archive may have, [`DEFAULT_MAX_ENTRIES`] if not set. Nested jars count on their own
+    pub max_entries: Option<usize>,
+    /// how long downloading an input that is a URL may take, no limit if not set
+    pub download_timeout: Option<Duration>,
+    /// how many downloads may run at the same time, across all threads. No limit if not set
+    pub max_concurrent_downloads: Option<usize>,
+    /// classes with a higher major version are an error, [`DEFAULT_MAX_CLASS_MAJOR`] if not set
+    pub max_class_major: Option<u16>,
+    /// log how it was decided what kind of file an input is, for `--explain`
+    pub explain: bool,
+    /// files have to be named like what they are instead of going by their content alone. Only local files have names
+    pub strict: bool,
+}
+
+/// Deep enough for a jar in a war in an ear in a tar
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 4;
+/// Classes are nowhere near this, but nested jars are read into memory and fat ones do get big
+pub const DEFAULT_MAX_ENTRY_BYTES: u64 = 256 * 1024 * 1024;
+/// Way more than the biggest real jars out there
+pub const DEFAULT_MAX_ENTRIES: usize = 1_000_000;
+
+impl ScanOptions {
+    /// The manifest is only read if anyone is interested in it
+    fn read_manifest(&self) -> bool {
+        self.manifest_version.is_some() || self.check_build_jdk
+    }
+
+    /// Only says anything with [`ScanOptions::explain`]
+    fn explain(&self, file: &str, message: fmt::Arguments) {
+        if self.explain {
+            info!("Explain {file}: {message}");
+        }
+    }
+
+    /// Whether the class `name` counts according to [`ScanOptions::include`] and [`ScanOptions::exclude`]
+    fn counts(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|glob| glob.is_match(name)))
+            && !self.exclude.iter().any(|glob| glob.is_match(name))
+    }
+
+    fn max_class_major(&self) -> u16 {
+        self.max_class_major.unwrap_or(DEFAULT_MAX_CLASS_MAJOR)
+    }
+
+    fn nesting_limit(&self) -> usize {
+        self.max_nesting_depth.unwrap_or(DEFAULT_MAX_NESTING_DEPTH)
+    }
+
+    /// The same for every archive, nested or not
+    fn limits<'a>(&self, archive: &'a mut dyn ArchiveReader) -> Capped<'a> {
+        Capped {
+            archive,
+            max_entries: self.max_entries.unwrap_or(DEFAULT_MAX_ENTRIES),
+            max_entry_bytes: self.max_entry_bytes.unwrap_or(DEFAULT_MAX_ENTRY_BYTES),
+        }
+    }
+}
+
+/// The class files of a jar, or any other kind of archive that is supported by [`open_archive`]
+struct ExtractedJar {
+    /// name of the entry and the class in it
+    classfiles: Vec<(String, JavaClass)>,
+    /// only read if [`ScanOptions::read_manifest`] says so
+    manifest: Option<Manifest>,
+    /// set if [`ScanOptions::stop_above`] kicked in, so `classfiles` doesn't contain everything
+    stopped_early: bool,
+    /// how many of the classes have debug info, only counted if [`ScanOptions::check_stripped`] is set
+    with_debug_info: usize,
+    /// classes and nested jars that were left out, as they are compressed (or encrypted) in a way that isn't supported
+    skipped: usize,
+}
+
+/// Whether reading an entry failed because of how it is compressed or encrypted, which doesn't say anything about the
+/// rest of the archive
+fn is_unsupported_entry(error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::Unsupported
+}
+
+impl ExtractedJar {
+    /// How many classes were looked at, nested jars included
+    fn class_count(&self) -> usize {
+        self.classfiles.len()
+    }
+
+    fn new(file: &str, options: &ScanOptions) -> Result<Self, ExtractedJarError> {
+        // only the headers of the classes are read, so most of the time goes into inflating entries. Every thread gets
+        // a handle of its own, a single ZipArchive can't be shared as reading an entry needs it as `mut`
+        if archive::is_zip(file) {
+            let threads = thread::available_parallelism().map_or(1, usize::from);
+            let slices =
+                archive::zip_slices(|| File::open(file), threads, archive::MIN_ENTRIES_TO_SPLIT)?;
+            // every slice only knows about its own entries
+            let max_entries = options.max_entries.unwrap_or(DEFAULT_MAX_ENTRIES);
+            let entries = slices.iter().map(ZipSlice::entries).sum();
+            archive::check_entries(entries, max_entries)?;
+            // tars are streamed, so only zips know how much there is to do
+            let progress = Progress::start(file, entries);
+            return Self::from_slices(slices, is_bundle(file), options, progress.as_ref());
+        }
+        let mut archive = open_archive(file)?;
+        if is_bundle(file) {
+            return Self::from_archive(&mut Bundle(&mut *archive), options);
+        }
+        Self::from_archive(&mut *archive, options)
+    }
+
+    /// Scans every slice on a thread of its own and puts the results back together in the order of the slices, so
+    /// this ends up with the same classes as [`ExtractedJar::from_archive`] would. With [`ScanOptions::stop_above`]
+    /// every slice stops on its own, which might leave out different classes, but the version is too high either way
+    /// A single slice is scanned right away
+    fn from_slices<R: Read + Seek + Send>(
+        mut slices: Vec<ZipSlice<R>>,
+        bundle: bool,
+        options: &ScanOptions,
+        progress: Option<&Progress>,
+    ) -> Result<Self, ExtractedJarError> {
+        let scan = |slice: &mut ZipSlice<R>| {
+            let mut counted;
+            let slice: &mut dyn ArchiveReader = match progress {
+                Some(progress) => {
+                    counted = Counted {
+                        archive: slice,
+                        progress,
+                    };
+                    &mut counted
+                }
+                None => slice,
+            };
+            if bundle {
+                Self::from_archive(&mut Bundle(slice), options)
+            } else {
+                Self::from_archive(slice, options)
+            }
+        };
+        if let [slice] = &mut slices[..] {
+            return scan(slice);
+        }
+        let results: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = slices
+                .into_iter()
+                .map(|mut slice| scope.spawn(move || scan(&mut slice)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .expect("scanning a slice of the archive panicked")
+                })
+                .collect()
+        });
+        let mut jar = Self {
+            classfiles: Vec::new(),
+            manifest: None,
+            stopped_early: false,
+            with_debug_info: 0,
+            skipped: 0,
+        };
+        for result in results {
+            let slice = match result {
+                Ok(slice) => slice,
+                // the classes might all be in other slices
+                Err(ExtractedJarError::NoClassFiles) => continue,
+                Err(e) => return Err(e),
+            };
+            jar.classfiles.extend(slice.classfiles);
+            jar.manifest = jar.manifest.or(slice.manifest);
+            jar.stopped_early |= slice.stopped_early;
+            jar.with_debug_info += slice.with_debug_info;
+            jar.skipped += slice.skipped;
+        }
+        if jar.classfiles.is_empty() {
+            return Err(ExtractedJarError::NoClassFiles);
+        }
+        Ok(jar)
+    }
+
+    /// Same as [`ExtractedJar::new`], but for jars that don't live in a file of their own
+    #[cfg(test)]
+    fn from_reader<R: Read + Seek>(
+        file: R,
+        options: &ScanOptions,
+    ) -> Result<Self, ExtractedJarError> {
+        Self::from_archive(&mut zip_from_reader(file)?, options)
+    }
+
+    fn from_archive(
+        archive: &mut dyn ArchiveReader,
+        options: &ScanOptions,
+    ) -> Result<Self, ExtractedJarError> {
+        Self::from_nested_archive(archive, options, 0)
+    }
+
+    /// `depth` is how many jars the archive is nested in
+    fn from_nested_archive(
+        archive: &mut dyn ArchiveReader,
+        options: &ScanOptions,
+        depth: usize,
+    ) -> Result<Self, ExtractedJarError> {
+        let archive = &mut options.limits(archive);
+        let scan_nested_jars = archive.scan_nested_jars() || options.nested_jars;
+
+        let multi_release = |name: &str| options.multi_release && is_multi_release_class(name);
+        let meta_inf = |name: &str| options.include_meta_inf && is_class_file(name);
+        let preview_only = |name: &str| options.fail_on_preview && is_multi_release_class(name);
+        let mut classfiles = Vec::new();
+        let mut manifest = None;
+        let mut stopped_early = false;
+        let mut with_debug_info = 0;
+        let mut skipped = 0;
+        // classes that only count because of --include-meta-inf
+        let mut from_meta_inf = 0;
+        let above_limit = |class: &JavaClass| {
+            options
+                .stop_above
+                .is_some_and(|max| *JavaVersion::from(class.clone()) > max)
+        };
+
+        debug!("Trying to get all relevant files in the archive");
+        archive.for_each_entry(&mut |name, entry| {
+            if is_class_file_in_jar(name) || multi_release(name) || meta_inf(name) {
+                if !options.counts(name) {
+                    trace!("Leaving out {name}, because of --include or --exclude");
+                    return Ok(ControlFlow::Continue(()));
+                }
+                debug!("Trying to extract {name}");
+                if !is_class_file_in_jar(name) && !multi_release(name) {
+                    from_meta_inf += 1;
+                }
+                let javaclass = if options.check_stripped {
+                    JavaClass::with_debug_info(entry, options.max_class_major()).map(
+                        |(javaclass, debug_info)| {
+                            if debug_info {
+                                trace!("{name} has debug info");
+                                with_debug_info += 1;
+                            }
+                            javaclass
+                        },
+                    )
+                } else {
+                    JavaClass::with_max_major(entry, options.max_class_major())
+                };
+                let javaclass = match javaclass {
+                    Err(JavaClassError::Read(e)) if is_unsupported_entry(&e) => {
+                        warn!("Skipping {name}: {e}");
+                        skipped += 1;
+                        return Ok(ControlFlow::Continue(()));
+                    }
+                    javaclass => javaclass?,
+                };
+                if javaclass.is_preview() {
+                    if options.fail_on_preview {
+                        return Err(ExtractedJarError::Preview(name.to_owned()));
+                    }
+                    warn!("{name} was compiled with --enable-preview, only Java {} can run it", *JavaVersion::from(javaclass.clone()));
+                }
+                let above = above_limit(&javaclass);
+                // a lower class after one that is too high doesn't make it fine again
+                if above && !stopped_early {
+                    info!(
+                        "{name} has version {}, which is already too high. Not looking any further",
+                        JavaVersion::from(javaclass.clone())
+                    );
+                }
+                stopped_early |= above;
+                classfiles.push((name.to_owned(), javaclass));
+            } else if preview_only(name) {
+                debug!("Checking {name} for preview features");
+                // a broken class that doesn't count anyway isn't worth failing for
+                if JavaClass::new(entry).is_ok_and(|class| class.is_preview()) {
+                    return Err(ExtractedJarError::Preview(name.to_owned()));
+                }
+            } else if (scan_nested_jars || options.fail_on_preview)
+                && (name.ends_with(".jar") || is_bundle(name))
+            {
+                if depth == options.nesting_limit() {
+                    let max = options.nesting_limit();
+                    if scan_nested_jars {
+                        return Err(ExtractedJarError::NestedTooDeep {
+                            jar: name.to_owned(),
+                            max,
+                        });
+                    }
+                    debug!("Not checking {name} for preview features, it's nested more than {max} jar(s) deep");
+                    return Ok(ControlFlow::Continue(()));
+                }
+                debug!("Trying to extract nested jar {name}");
+                // ZipArchive wants to seek, so the jar has to be pulled into memory
+                let mut jar = Vec::new();
+                match entry.read_to_end(&mut jar) {
+                    Err(e) if is_unsupported_entry(&e) => {
+                        warn!("Skipping {name}: {e}");
+                        skipped += 1;
+                        return Ok(ControlFlow::Continue(()));
+                    }
+                    read => read?,
+                };
+                let nested = zip_from_reader(Cursor::new(jar)).and_then(|mut jar| {
+                    // the wars of an ear have their own libraries
+                    if is_bundle(name) {
+                        Self::from_nested_archive(&mut Bundle(&mut jar), options, depth + 1)
+                    } else {
+                        Self::from_nested_archive(&mut jar, options, depth + 1)
+                    }
+                });
+                match nested {
+                    // only looked at for --fail-if-any-preview, the classes don't count otherwise
+                    Ok(_) if !scan_nested_jars => {}
+                    Ok(jar) => {
+                        stopped_early |= jar.stopped_early;
+                        with_debug_info += jar.with_debug_info;
+                        skipped += jar.skipped;
+                        // same notation as in jar: URLs
+                        classfiles.extend(
+                            jar.classfiles
+                                .into_iter()
+                                .map(|(inner, class)| (format!("{name}!/{inner}"), class)),
+                        )
+                    }
+                    // a resource-only jar isn't a reason to give up on everything else
+                    Err(ExtractedJarError::NoClassFiles) => {
+                        debug!("{name} does not contain any class files, skipping")
+                    }
+                    Err(ExtractedJarError::Preview(inner)) => {
+                        return Err(ExtractedJarError::Preview(format!("{name}!/{inner}")));
+                    }
+                    Err(ExtractedJarError::NestedTooDeep { jar, max }) if scan_nested_jars => {
+                        return Err(ExtractedJarError::NestedTooDeep {
+                            jar: format!("{name}!/{jar}"),
+                            max,
+                        });
+                    }
+                    Err(ExtractedJarError::TooLarge { entry, limit }) if scan_nested_jars => {
+                        return Err(ExtractedJarError::TooLarge {
+                            entry: nested_entry(name, &entry),
+                            limit,
+                        });
+                    }
+                    // a jar that only got looked at for preview classes shouldn't fail the whole scan
+                    Err(e) if !scan_nested_jars => debug!("Failed to read {name}: {e}"),
+                    Err(e) => return Err(e),
+                }
+            } else if options.read_manifest() && name == MANIFEST_PATH {
+                debug!("Reading manifest");
+                let mut content = String::new();
+                entry.read_to_string(&mut content)?;
+                manifest = Some(Manifest::parse(&content));
+            }
+            // the manifest might not have been read so far, which would look like it's missing
+            if stopped_early && (!options.read_manifest() || manifest.is_some()) {
+                return Ok(ControlFlow::Break(()));
+            }
+            Ok(ControlFlow::Continue(()))
+        })?;
+        if from_meta_inf > 0 {
+            debug!(
+                "Considered {from_meta_inf} more class file(s) in META-INF because of --include-meta-inf"
+            );
+        }
+
+        // Technically, Jar files might not contain any classes. But no idea what to do with that in this context
+        if classfiles.is_empty() {
+            // when in doubt, bubble the problem up to the call site!
+            // https://en.wikipedia.org/wiki/Somebody_else%27s_problem
+            return Err(ExtractedJarError::NoClassFiles);
+        }
+
+        // This is definitely an archive with class files! Don't know if that is meaningfully different from a Jar. Assuming it isn't...
+        Ok(Self {
+            classfiles,
+            manifest,
+            stopped_early,
+            with_debug_info,
+            skipped,
+        })
+    }
+
+    /// Makes sure that all classes of a jar have the same version. Nested jars are checked on their own, as they might
+    /// just as well come from somewhere else
+    fn check_uniform(&self, file: &str) -> Result<(), ExtractedJarError> {
+        // first class for every version per jar, as an example
+        let mut jars: BTreeMap<&str, BTreeMap<u16, &str>> = BTreeMap::new();
+        for (name, class) in &self.classfiles {
+            let jar = name.rsplit_once("!/").map_or("", |(jar, _)| jar);
+            jars.entry(jar)
+                .or_default()
+                .entry(class.major)
+                .or_insert(name);
+        }
+        match jars.into_iter().find(|(_, versions)| versions.len() > 1) {
+            Some((jar, versions)) => Err(ExtractedJarError::NotUniform {
+                jar: if jar.is_empty() {
+                    file.to_owned()
+                } else {
+                    format!("{file}!/{jar}")
+                },
+                examples: versions
+                    .into_iter()
+                    .map(|(major, name)| {
+                        format!(
+                            "Java {} (e.g. {name})",
+                            *JavaVersion::from(JavaClass { major, minor: 0 })
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// The class with the highest version and that version. The first one wins if there are several
+    fn max_with_name(&self) -> Option<(&str, JavaVersion)> {
+        self.classfiles
+            .iter()
+            .rev()
+            .max_by_key(|(_, class)| class.major)
+            .map(|(name, class)| (name.as_str(), class.clone().into()))
+    }
+
+    /// Warns if the classes aren't for the JDK that the manifest says built them. Classes for an older version are
+    /// mostly fine (`--release`), but might as well mean that the manifest is from somewhere else
+    fn check_build_jdk(&self, file: &str, version: &JavaVersion) {
+        match self.manifest.as_ref().and_then(Manifest::build_jdk) {
+            Some((key, jdk)) if jdk != *version => warn!(
+                "{file}: the manifest says it was built with JDK {} ({key}), but the classes are for {version}",
+                *jdk
+            ),
+            Some(_) => {}
+            None => debug!("{file} doesn't say which JDK built it"),
+        }
+    }
+
+    /// Checks the Implementation-Version of the manifest against the pattern from [`ScanOptions::manifest_version`]
+    fn check_manifest_version(&self, expected: &Regex) -> Result<(), ExtractedJarError> {
+        let actual = self
+            .manifest
+            .as_ref()
+            .and_then(|manifest| manifest.get("Implementation-Version"));
+        match actual {
+            Some(actual) if expected.is_match(actual) => Ok(()),
+            Some(actual) => Err(ExtractedJarError::ManifestVersionMismatch {
+                expected: expected.to_string(),
+                actual: actual.to_owned(),
+            }),
+            None => Err(ExtractedJarError::MissingManifestVersion(
+                expected.to_string(),
+            )),
+        }
+    }
+}
+
+/// Opens a zip that doesn't live in a file of its own, like a jar inside of another archive
+fn zip_from_reader<R: Read + Seek>(mut file: R) -> Result<zip::ZipArchive<R>, ExtractedJarError> {
+    let mut buffer = Vec::with_capacity(4);
+
+    let read_bytes = (&mut file).take(4).read_to_end(&mut buffer)?;
+    if read_bytes != 4 {
+        return Err(ExtractedJarError::InsufficientBytes(read_bytes));
+    }
+
+    // not sure if this is even necessary. ZipArchive::new most likely does something like this as well
+    if buffer != MAGIC_ZIP_HEADER {
+        return Err(ExtractedJarError::NotAJar);
+    }
+    // Technically we don't know if the jar is actually a jar
+    // We just know that the file is a zip file (or, well, we assume it is because the magic bytes said so)
+    // got here, now we can be pretty sure that this is a zip file! Wait, this isn't really what we were looking for...
+    Ok(zip::ZipArchive::new(file)?)
+}
+
+/// Whether the entry is a .class file outside of a META-INF directory.
+fn is_class_file_in_jar(name: &str) -> bool {
+    is_class_file(name)
+        // META-INF can contain .class files, no idea what they do
+        // Pretend/hope that they don't matter, unless --include-meta-inf says otherwise
+        && !name.starts_with("META-INF")
+}
+
+/// Whether the entry is a .class file, wherever it is
+fn is_class_file(name: &str) -> bool {
+    name.ends_with(".class")
+}
+
+/// Whether the entry is a class for a specific Java version of a multi-release jar, like
+/// `META-INF/versions/17/com/example/Foo.class`
+fn is_multi_release_class(name: &str) -> bool {
+    name.strip_prefix("META-INF/versions/")
+        .and_then(|rest| rest.split_once('/'))
+        .is_some_and(|(version, class)| {
+            !version.is_empty()
+                && version.chars().all(|c| c.is_ascii_digit())
+                && class.ends_with(".class")
+        })
+}
+
+fn handle_class<P: AsRef<Path>>(
+    file: P,
+    options: &ScanOptions,
+) -> Result<(JavaClass, Option<usize>), JavaClassError> {
+    let path = file.as_ref();
+    let file = File::open(path)?;
+    debug!("Read {file:?}");
+    read_class(file, &path.display().to_string(), options)
+}
+
+/// What [`handle_class`] does once the file is open, `name` is only there for errors
+fn read_class(
+    file: impl Read,
+    name: &str,
+    options: &ScanOptions,
+) -> Result<(JavaClass, Option<usize>), JavaClassError> {
+    let (class, debug_info) = if options.check_stripped {
+        let (class, debug_info) = JavaClass::with_debug_info(file, options.max_class_major())?;
+        (class, Some(usize::from(debug_info)))
+    } else {
+        (
+            JavaClass::with_max_major(file, options.max_class_major())?,
+            None,
+        )
+    };
+    if class.is_preview() {
+        if options.fail_on_preview {
+            return Err(JavaClassError::Preview(name.to_owned()));
+        }
+        warn!(
+            "{name} was compiled with --enable-preview, only Java {} can run it",
+            *JavaVersion::from(class.clone())
+        );
+    }
+    Ok((class, debug_info))
+}
+
+/// What came out of reading one of the inputs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanResult {
+    /// the highest version of all classes
+    pub version: JavaVersion,
+    /// the lowest version of all classes
+    pub lowest: JavaVersion,
+    /// every class in an archive, only filled if [`ScanOptions::keep_classes`] is set
+    pub classes: Vec<(String, JavaVersion)>,
+    /// number of classes with debug info, if [`ScanOptions::check_stripped`] is set
+    pub with_debug_info: Option<usize>,
+    /// entry names of the classes that were compiled with `--enable-preview`. For a class file that is one itself,
+    /// this is a single empty name
+    pub preview: Vec<String>,
+    /// how many classes there are of every major version (52 for Java 8 and so on). Only complete if the archive was
+    /// read completely, see [`ScanOptions::stop_above`]
+    pub histogram: BTreeMap<u16, usize>,
+    /// entries of an archive that couldn't be read because of how they are compressed or encrypted, the versions
+    /// only go by everything else
+    pub skipped_entries: usize,
+}
+
+impl ScanResult {
+    fn of_class(class: JavaClass, with_debug_info: Option<usize>) -> Self {
+        let preview = class.is_preview().then(String::new).into_iter().collect();
+        let histogram = BTreeMap::from([(class.major, 1)]);
+        let version = JavaVersion::from(class);
+        info!("Class version is {}", version);
+        Self {
+            lowest: version.clone(),
+            version,
+            classes: Vec::new(),
+            with_debug_info,
+            preview,
+            histogram,
+            skipped_entries: 0,
+        }
+    }
+}
+
+/// Removes whatever `pattern` matches at the start of the entry name, and at the start of every entry in a nested jar
+fn strip_entry_prefix(name: &str, pattern: &Regex) -> String {
+    name.split("!/")
+        .map(|part| match pattern.find(part) {
+            Some(found) if found.start() == 0 => &part[found.end()..],
+            _ => part,
+        })
+        .collect::<Vec<_>>()
+        .join("!/")
+}
+
+/// `entry` of the archive `jar`, or `jar` itself if there's no entry
+fn nested_entry(jar: &str, entry: &str) -> String {
+    if entry.is_empty() {
+        jar.to_owned()
+    } else {
+        format!("{jar}!/{entry}")
+    }
+}
+
+/// Scans a jar or any other supported archive, like a `.tar.gz`
+pub fn process_archive(file: &str, options: &ScanOptions) -> Result<ScanResult, ExtractedJarError> {
+    info!("Handling archive {file}");
+    archive_result(file, ExtractedJar::new(file, options), options)
+}
+
+/// Everything [`process_archive`] checks and collects once the archive went through [`ExtractedJar`]
+fn archive_result(
+    file: &str,
+    extracted: Result<ExtractedJar, ExtractedJarError>,
+    options: &ScanOptions,
+) -> Result<ScanResult, ExtractedJarError> {
+    let extracted = extracted.map_err(|e| match e {
+        ExtractedJarError::Preview(class) => ExtractedJarError::Preview(format!("{file}!/{class}")),
+        ExtractedJarError::NestedTooDeep { jar, max } => ExtractedJarError::NestedTooDeep {
+            jar: format!("{file}!/{jar}"),
+            max,
+        },
+        ExtractedJarError::TooLarge { entry, limit } => ExtractedJarError::TooLarge {
+            entry: nested_entry(file, &entry),
+            limit,
+        },
+        e => e,
+    })?;
+    if let Some(expected) = &options.manifest_version {
+        extracted.check_manifest_version(expected)?;
+    }
+
+    if options.uniform {
+        extracted.check_uniform(file)?;
+    }
+    if !options.package_floors.is_empty() {
+        let classes = extracted
+            .classfiles
+            .iter()
+            .map(|(name, class)| (name.as_str(), class.clone().into()));
+        floor::check(&options.package_floors, classes).map_err(|violations| {
+            ExtractedJarError::BelowPackageFloor {
+                jar: file.to_owned(),
+                violations,
+            }
+        })?;
+    }
+    debug!(
+        "{file}: inspected {} class file(s){}",
+        extracted.class_count(),
+        if extracted.stopped_early {
+            ", stopped at the first one above the maximum"
+        } else {
+            ""
+        }
+    );
+    let (lowest, version) =
+        JavaVersion::version_range(extracted.classfiles.iter().map(|(_, class)| class.clone()));
+    if *version == 0 {
+        return Err(ExtractedJarError::NoClassFiles);
+    }
+    if let Some((name, max)) = extracted.max_with_name() {
+        debug!("max version {max} comes from {name}");
+    }
+    if options.check_build_jdk {
+        extracted.check_build_jdk(file, &version);
+    }
+    if extracted.skipped > 0 {
+        warn!(
+            "{file}: skipped {} entr(y/ies) that are compressed or encrypted in an unsupported way, the version only goes by the rest",
+            extracted.skipped
+        );
+    }
+    let preview = extracted
+        .classfiles
+        .iter()
+        .filter(|(_, class)| class.is_preview())
+        .map(|(name, _)| name.clone())
+        .collect();
+    let mut histogram = BTreeMap::new();
+    for (_, class) in &extracted.classfiles {
+        *histogram.entry(class.major).or_default() += 1;
+    }
+    let classes = if options.keep_classes {
+        extracted
+            .classfiles
+            .into_iter()
+            .map(|(name, class)| match &options.strip_entry_prefix {
+                Some(pattern) => (strip_entry_prefix(&name, pattern), class.into()),
+                None => (name, class.into()),
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    Ok(ScanResult {
+        version,
+        lowest,
+        classes,
+        with_debug_info: options.check_stripped.then_some(extracted.with_debug_info),
+        preview,
+        histogram,
+        skipped_entries: extracted.skipped,
+    })
+}
+
+/// Up to `count` bytes from the start of `file` as a hex dump, 16 bytes per line with their ASCII next to them
+fn head_bytes(file: &str, count: u64) -> io::Result<String> {
+    let mut bytes = Vec::new();
+    File::open(file)?.take(count).read_to_end(&mut bytes)?;
+    let lines: Vec<_> = bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(line, chunk)| {
+            let hex: Vec<_> = chunk.iter().map(|byte| format!("{byte:02x}")).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&byte| {
+                    if byte.is_ascii_graphic() || byte == b' ' {
+                        byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!("{:08x}  {:<47}  |{ascii}|", line * 16, hex.join(" "))
+        })
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+/// The class files in an archive, without reading any of them. For `--names-only`
+fn class_names(
+    file: &str,
+    timeout: Option<Duration>,
+    include_meta_inf: bool,
+) -> anyhow::Result<Vec<String>> {
+    debug!("Listing classes of {file}");
+    let mut archive = if file == STDIN {
+        archive_from_reader(read_stdin()?, false)?
+    } else if download::is_url(file) {
+        archive_from_reader(download::fetch(file, timeout)?, false)?
+    } else {
+        open_archive(file)?
+    };
+    let mut names = archive.entry_names()?;
+    names.retain(|name| {
+        if include_meta_inf {
+            is_class_file(name)
+        } else {
+            is_class_file_in_jar(name)
+        }
+    });
+    Ok(names)
+}
+
+/// The path that stands for stdin
+pub const STDIN: &str = "-";
+
+/// All of stdin, in memory as zips want to seek
+fn read_stdin() -> io::Result<Cursor<Vec<u8>>> {
+    let mut data = Vec::new();
+    io::stdin().lock().read_to_end(&mut data)?;
+    debug!("Read {} bytes from stdin", data.len());
+    Ok(Cursor::new(data))
+}
+
+/// Whether the input is an actual file, and not stdin or a URL
+fn is_local(file: &str) -> bool {
+    file != STDIN && !download::is_url(file)
+}
+
+/// What `--names-only` prints: a class file is just itself, archives are listed by the classes in them. Like with
+/// scanning, the content decides which one it is
+fn names_only(
+    file: &str,
+    timeout: Option<Duration>,
+    include_meta_inf: bool,
+) -> anyhow::Result<Vec<String>> {
+    if is_local(file) && detect_format(file)? == Format::Class {
+        return Ok(vec![file.to_owned()]);
+    }
+    Ok(class_names(file, timeout, include_meta_inf)?
+        .into_iter()
+        .map(|name| format!("{file}!/{name}"))
+        .collect())
+}
+
+/// Scans a class or an archive that is piped in or was downloaded. What the name ends with can't be trusted,
+/// so the magic bytes decide
+fn scan_in_memory(
+    input: &str,
+    data: Cursor<Vec<u8>>,
+    options: &ScanOptions,
+) -> anyhow::Result<ScanResult> {
+    let from = if input == STDIN { "stdin" } else { input };
+    if data.get_ref().is_empty() {
+        bail!(EmptyInput(from.to_owned()));
+    }
+    let Some(format) = Format::of(data.get_ref()) else {
+        bail!(UnknownFormat(from.to_owned()));
+    };
+    options.explain(input, format_args!("detected {format}"));
+    if format == Format::Class {
+        info!("Reading a class from {from}");
+        let (class, with_debug_info) = read_class(data, input, options)?;
+        return Ok(ScanResult::of_class(class, with_debug_info));
+    }
+    info!("Reading an archive from {from}");
+    let extracted = archive_from_reader(data, false)
+        .and_then(|mut archive| ExtractedJar::from_archive(&mut *archive, options));
+    Ok(archive_result(input, extracted, options)?)
+}
+
+/// What a file is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Class,
+    Zip,
+    Tar,
+    SevenZ,
+}
+
+impl Format {
+    /// Goes by the magic bytes alone, `head` should be 262 bytes to catch tars
+    fn of(head: &[u8]) -> Option<Self> {
+        if head.starts_with(&MAGIC_CLASS_HEADER) {
+            Some(Self::Class)
+        } else if head.starts_with(&MAGIC_ZIP_HEADER) {
+            Some(Self::Zip)
+        } else if has_tar_magic(head) {
+            Some(Self::Tar)
+        } else if head.starts_with(&MAGIC_7Z_HEADER) {
+            Some(Self::SevenZ)
+        } else {
+            None
+        }
+    }
+
+    /// What the name suggests, which might very well be wrong
+    fn by_name(file: &str) -> Option<Self> {
+        match Path::new(file).extension().and_then(|s| s.to_str()) {
+            _ if is_tar(file) => Some(Self::Tar),
+            Some("jar" | "war" | "ear" | "aar" | "zip") => Some(Self::Zip),
+            Some("7z") => Some(Self::SevenZ),
+            Some("class") => Some(Self::Class),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Class => "a class",
+            Self::Zip => "a zip archive",
+            Self::Tar => "a tar archive",
+            Self::SevenZ => "a 7z archive",
+        })
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("{0} is neither a class file nor a zip, tar or 7z archive, as far as its first bytes go")]
+pub(crate) struct UnknownFormat(String);
+
+/// A file that `--strict` doesn't let through, as it would take a guess to scan it
+#[derive(Error, Debug)]
+pub(crate) enum NotStrict {
+    #[error("{0} isn't named like a class file or an archive, which --strict requires")]
+    Unnamed(String),
+    #[error("{file} is named like {named}, but is {format}, which --strict doesn't allow")]
+    Mismatch {
+        file: String,
+        named: Format,
+        format: Format,
+    },
+}
+
+/// Nothing at all to go by, which is worth saying instead of complaining about missing bytes
+#[derive(Error, Debug)]
+#[error("{0} is empty")]
+pub(crate) struct EmptyInput(String);
+
+/// Reads the first bytes of a file to find out what it is. Only old tars, which don't have any magic bytes, go by
+/// their name
+fn detect_format(file: &str) -> anyhow::Result<Format> {
+    let mut head = Vec::with_capacity(262);
+    File::open(file)
+        .and_then(|file| file.take(262).read_to_end(&mut head))
+        .with_context(|| format!("Failed to read {file}"))?;
+    if head.is_empty() {
+        bail!(EmptyInput(file.to_owned()));
+    }
+    match Format::of(&head) {
+        Some(format) => Ok(format),
+        None if is_tar(file) => Ok(Format::Tar),
+        None => Err(UnknownFormat(file.to_owned()).into()),
+    }
+}
+
+/// Figures out what kind of file this is and scans it accordingly. The content decides, a jar that is named like a
+/// class is still a jar
+fn scan_file(file: &str, options: &ScanOptions) -> anyhow::Result<ScanResult> {
+    let explain = |message: fmt::Arguments| options.explain(file, message);
+    if file == STDIN {
+        explain(format_args!("stdin has no name, going by the magic bytes"));
+        return scan_in_memory(file, read_stdin()?, options);
+    }
+    if download::is_url(file) {
+        explain(format_args!(
+            "a URL, downloading it and going by the magic bytes"
+        ));
+        let data = {
+            let _permit = options
+                .max_concurrent_downloads
+                .map(|limit| download::DOWNLOADS.acquire(limit));
+            download::fetch(file, options.download_timeout)?
+        };
+        return scan_in_memory(file, data, options);
+    }
+    let format = detect_format(file);
+    match (&format, Format::by_name(file)) {
+        (Ok(format), Some(named)) if *format != named => {
+            debug!("{file} is named like {named}, but is {format}");
+            explain(format_args!(
+                "detected {format}, even though the name says {named}. The content wins"
+            ));
+        }
+        (Ok(format), Some(_)) => explain(format_args!("detected {format}, like the name says")),
+        (Ok(format), None) => explain(format_args!("detected {format}, the name doesn't say")),
+        (Err(e), _) => explain(format_args!("{e}")),
+    }
+    let format = format?;
+    if options.strict {
+        match Format::by_name(file) {
+            None => bail!(NotStrict::Unnamed(file.to_owned())),
+            Some(named) if named != format => bail!(NotStrict::Mismatch {
+                file: file.to_owned(),
+                named,
+                format,
+            }),
+            Some(_) => {}
+        }
+    }
+    let result: anyhow::Result<_> = match format {
+        Format::Class => process_class(file, options).map_err(|e| e.into()),
+        Format::Zip | Format::Tar | Format::SevenZ => {
+            process_archive(file, options).map_err(|e| e.into())
+        }
+    };
+    match &result {
+        Ok(_) => explain(format_args!("read it as {format}")),
+        Err(e) => explain(format_args!("failed to read it as {format}: {e}")),
+    }
+    result
+}
+
+/// Whether reading a file failed because it's not readable for us, which shouldn't end a big scan
+fn is_permission_denied(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<io::Error>()
+            .is_some_and(|e| e.kind() == io::ErrorKind::PermissionDenied)
+    })
+}
+
+/// Names the file an error is about, as not every error does that on its own and with many inputs it's anyone's guess
+/// which one "Not a java class" was
+fn in_file<T>(result: anyhow::Result<T>, file: &str) -> anyhow::Result<T> {
+    result.with_context(|| format!("while processing {file}"))
+}
+
+#[derive(Error, Debug)]
+#[error("scanning took longer than {0:?}")]
+struct TimedOut(Duration);
+
+/// Runs `scan` on a thread of its own and gives up on it after `timeout`.
+/// There is no way to stop the thread, so it keeps going in the background until it's done or the process exits
+fn with_timeout<T: Send + 'static>(
+    timeout: Duration,
+    scan: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, TimedOut> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        // nobody might be listening anymore, which is fine
+        let _ = sender.send(logging::capture(scan));
+    });
+    let (result, logged) = receiver
+        .recv_timeout(timeout)
+        .map_err(|_| TimedOut(timeout))?;
+    // logged on the thread that asked, which might be capturing as well
+    logging::replay(logged);
+    Ok(result)
+}
+
+/// Reads a single class file
+pub fn process_class(file: &str, options: &ScanOptions) -> Result<ScanResult, JavaClassError> {
+    info!("Reading from {file}");
+    let (class, with_debug_info) = handle_class(file, options)?;
+    Ok(ScanResult::of_class(class, with_debug_info))
+}
+
+/// `--max` and `--min`
+struct Limits {
+    /// as it was given, not taking `exclusive` into account
+    max: Option<u16>,
+    /// `--max-exclusive`
+    exclusive: bool,
+    min: Option<u16>,
+    /// whether to say how to get below the maximum
+    hint: bool,
+}
+
+impl Limits {
+    /// The highest version that is still fine
+    fn allowed(&self) -> Option<u16> {
+        self.max.map(|max| {
+            if self.exclusive {
+                max.saturating_sub(1)
+            } else {
+                max
+            }
+        })
+    }
+
+    /// What is wrong with the versions that were found to be too high and too low. One file can be both at the same
+    /// time, and different files might each have one of the problems, so there can be a message for either side
+    fn violations(
+        &self,
+        too_high: BTreeSet<(String, JavaVersion)>,
+        too_low: BTreeSet<(String, JavaVersion)>,
+    ) -> Vec<String> {
+        let mut violations = Vec::new();
+        if let Some(max) = self.max
+            && !too_high.is_empty()
+        {
+            // the set already took care of duplicates and the order, by path and then version
+            let comparison = if self.exclusive {
+                "at or above"
+            } else {
+                "higher than"
+            };
+            let hint = match (
+                too_high.iter().map(|(_, version)| version).max(),
+                self.allowed(),
+            ) {
+                (Some(highest), Some(allowed)) if self.hint => {
+                    format!("\n{}", highest.remediation_hint(allowed))
+                }
+                _ => String::new(),
+            };
+            violations.push(format!(
+                "Found class(es) {comparison} the given maximum of {max}!{}{hint}",
+                files_with_versions(&too_high)
+            ));
+        }
+        if let Some(min) = self.min
+            && !too_low.is_empty()
+        {
+            violations.push(format!(
+                "Found class(es) lower than the given minimum of {min}!{}",
+                files_with_versions(&too_low)
+            ));
+        }
+        violations
+    }
+}
+
+/// One `path: version` line for each file, for the messages of [`Limits::violations`]
+fn files_with_versions(files: &BTreeSet<(String, JavaVersion)>) -> String {
+    files
+        .iter()
+        .map(|(path, version)| format!("\n  {path}: {version}"))
+        .collect()
+}
+
+/// Writes to a temporary file next to `path` first, so nobody ever sees half of the content
+fn write_atomically(path: &Path, content: &str) -> io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".{}.tmp", std::process::id()));
+    fs::write(&temp, content)?;
+    fs::rename(&temp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+/// Where `file` really is, if that's somewhere else because of symlinks
+fn real_path(file: &str) -> io::Result<Option<String>> {
+    let real = fs::canonicalize(file)?;
+    // without any symlinks, the only difference would be `.` and `..`
+    let given = std::path::absolute(file)?;
+    if given.components().eq(real.components())
+        || !Path::new(file).ancestors().any(|path| path.is_symlink())
+    {
+        return Ok(None);
+    }
+    Ok(Some(real.display().to_string()))
+}
+
+/// Everything besides the report itself that decides what the output looks like
+struct OutputContext<'a> {
+    text: TextOptions,
+    /// the report came out of `--merge`
+    merged: bool,
+    zero_class_jars: Option<&'a [String]>,
+    /// set if structured output should only contain the files above the maximum. This is the summary of all files
+    only_violations: Option<&'a Summary>,
+    aggregate: Aggregate,
+    /// files that couldn't be scanned, only for JSON
+    errors: &'a [FileError],
+}
+
+/// The final report in the given format
+fn render_output(format: OutputFormat, report: &[FileReport], context: &OutputContext) -> String {
+    let violations = || -> Vec<FileReport> {
+        report
+            .iter()
+            .filter(|file| {
+                context
+                    .text
+                    .max
+                    .is_some_and(|max| file.version.over_max(max).is_some())
+            })
+            .cloned()
+            .collect()
+    };
+    match format {
+        // run() makes sure there is a maximum
+        OutputFormat::Text if context.aggregate == Aggregate::Classes => {
+            render_classes_above_text(report, context.text.max.unwrap_or_default())
+        }
+        OutputFormat::Json if context.aggregate == Aggregate::Classes => pretty(
+            &render_classes_above_json(report, context.text.max.unwrap_or_default()),
+        ),
+        OutputFormat::Text if context.aggregate != Aggregate::List => {
+            render_aggregate_text(report, context.aggregate, context.text.max)
+        }
+        OutputFormat::Json if context.aggregate != Aggregate::List => {
+            pretty(&render_aggregate_json(report, context.aggregate))
+        }
+        OutputFormat::Text => {
+            let mut out = render_text(report, &context.text);
+            if let Some(summary) = Summary::of(report).filter(|_| context.merged) {
+                out.push_str(&render_summary(&summary));
+            }
+            if let Some(jars) = context.zero_class_jars {
+                out.push_str(&render_zero_class_jars(jars));
+            }
+            out
+        }
+        OutputFormat::Json => {
+            let violations = context.only_violations.map(|_| violations());
+            let merged = Summary::of(report).filter(|_| context.merged);
+            let mut json = render_json(violations.as_deref().unwrap_or(report));
+            push_errors(&mut json, context.errors);
+            json.summary = context.only_violations.or(merged.as_ref());
+            json.zero_class_jars = context.zero_class_jars;
+            pretty(&json)
+        }
+        OutputFormat::TreemapJson if context.only_violations.is_some() => {
+            pretty(&render_treemap(&violations()))
+        }
+        OutputFormat::TreemapJson => pretty(&render_treemap(report)),
+        OutputFormat::Prometheus => render_prometheus(report, context.text.max),
+    }
+}
+
+/// When all that matters is whether something is too high, there is no need to look at everything. Anything that
+/// needs the classes (or the version) of a whole file can't stop at the first one above the maximum though
+fn needs_inventory(args: &Cli) -> bool {
+    args.sort.is_some()
+    // the lowest version needs every class as well
+    || args.min.is_some()
+    || args.aggregate != Aggregate::List
+    || args.group_by_version
+    || args.summary
+    || args.histogram
+    || args.count
+    || args.range
+    || args.list
+    // the highest version is what the JDK has to match
+    || args.check_manifest
+    // has to find all of them
+    || args.fail_on_preview
+    || args.assert_uniform_per_jar
+    || args.format != OutputFormat::Text
+    || !args.reports.is_empty()
+    || !args.policies.is_empty()
+    || !args.package_floors.is_empty()
+    // prints the highest version, not just whether it's too high
+    || args.version_only
+    // the JDK to recommend is the one for the highest version
+    || args.recommend_jdk
+    // the summary has the highest version in it
+    || args.batch_summary_json.is_some()
+    // counts the classes with debug info in all of them
+    || args.check_stripped
+}
+
+/// Everything the binary does, which is only in here so that it can use the same (crate private) things as the library
+#[doc(hidden)]
+pub fn run() -> anyhow::Result<()> {
+    let mut args = Cli::new()?;
+    if !args.quiet {
+        logging::init(args.verbosity, args.structured_stdout);
+    }
+    if args.json_schema {
+        println!("{}", pretty(&report::json_schema()));
+        return Ok(());
+    }
+    let cache_dir = args.cache_dir.clone().or_else(cache::default_dir);
+    if args.clear_cache
+        && let Some(dir) = &cache_dir
+    {
+        cache::clear(dir)
+            .with_context(|| format!("Failed to clear the cache in {}", dir.display()))?;
+        info!("Cleared the cache in {}", dir.display());
+    }
+    if args.files.is_empty() {
+        return Ok(());
+    }
+    args.files = walk::expand_globs(args.files)?;
+    trace!("{args:?}");
+
+    let max = if args.compare_to_runtime {
+        let runtime = runtime::runtime_version()?;
+        info!("Installed runtime is {}", JavaVersion(runtime));
+        Some(runtime)
+    } else {
+        args.max
+    };
+    let max = match &args.baseline {
+        Some(baseline) => {
+            let version = scan_file(baseline, &ScanOptions::default())
+                .with_context(|| format!("Failed to scan the baseline {baseline}"))?
+                .version;
+            info!("Baseline {baseline} is {version}");
+            Some(max.map_or(*version, |max| max.min(*version)))
+        }
+        None => max,
+    };
+    let limits = Limits {
+        max,
+        exclusive: args.max_exclusive,
+        min: args.min,
+        hint: !args.no_hint,
+    };
+    // from here on, the maximum is the highest version that is still fine
+    let max = limits.allowed();
+    let formats_in_use: Vec<_> = std::iter::once(args.format)
+        .chain(args.reports.iter().map(|target| target.format))
+        .collect();
+    if args.aggregate != Aggregate::List
+        && formats_in_use
+            .iter()
+            .any(|format| !matches!(format, OutputFormat::Text | OutputFormat::Json))
+    {
+        bail!("--aggregate only works for text and json output");
+    }
+    if args.aggregate == Aggregate::Classes && max.is_none() {
+        bail!("--aggregate classes needs --max (or --compare-to-runtime) to know what is too high");
+    }
+    let inventory = needs_inventory(&args);
+    let options = ScanOptions {
+        manifest_version: args.require_manifest_version,
+        check_build_jdk: args.check_manifest,
+        // verifying without --exact is just --max with a different message
+        stop_above: max
+            .or(args
+                .verify
+                .filter(|verify| !verify.exact)
+                .map(|verify| verify.expect))
+            .filter(|_| !inventory && !args.full_scan),
+        keep_classes: args.list
+            || args.aggregate == Aggregate::Classes
+            || args.format == OutputFormat::TreemapJson
+            || args
+                .reports
+                .iter()
+                .any(|target| target.format == OutputFormat::TreemapJson),
+        check_stripped: args.check_stripped,
+        strip_entry_prefix: args.entry_regex_strip,
+        uniform: args.assert_uniform_per_jar,
+        fail_on_preview: args.fail_if_any_preview,
+        package_floors: args.package_floors,
+        multi_release: args.multi_release,
+        include_meta_inf: args.include_meta_inf,
+        include: args.include,
+        exclude: args.exclude,
+        nested_jars: args.nested_jars,
+        max_nesting_depth: Some(args.max_nesting_depth),
+        max_entry_bytes: Some(args.max_entry_bytes),
+        max_entries: Some(args.max_entries),
+        download_timeout: args.timeout,
+        max_concurrent_downloads: args.concurrency_limit,
+        max_class_major: Some(args.max_class_major),
+        explain: args.explain,
+        strict: args.strict,
+    };
+    let mut too_high = BTreeSet::new();
+    let mut too_low = BTreeSet::new();
+    let mut report = Vec::new();
+    // kept on the side, as the report might not have everything in it
+    let mut highest = JavaVersion(0);
+    // plain JSON output doesn't need anything but the current file, so it's written as it comes instead of collecting
+    // everything first. Anything that sorts, merges or verifies needs the whole report
+    let mut stream = (args.format == OutputFormat::Json
+        && args.reports.is_empty()
+        && args.sort.is_none()
+        && args.aggregate == Aggregate::List
+        && !args.merge
+        && args.verify.is_none())
+    .then(|| StreamingObject::new(io::stdout(), &header_fields(), "files"))
+    .transpose()?;
+    let mut zero_class_jars = args.report_zero_class_jars.then(Vec::new);
+    // JSON has a place for files that failed, so they don't have to end the run right away
+    let collect_errors = args.keep_going || formats_in_use.contains(&OutputFormat::Json);
+    let mut errors = Vec::new();
+    // with --keep-going, listed in the end no matter if they are part of the output as well
+    let mut failures = Vec::new();
+    // for --fail-on-preview
+    let mut preview_classes = Vec::new();
+    let mut policies = Policies::new(args.policies);
+    // --only-violations leaves files out, so the summary has to be counted on the side
+    let mut histogram =
+        (args.only_violations || args.batch_summary_json.is_some()).then(BTreeMap::new);
+    // for --count, which might count classes instead of files
+    let mut counts = args.count.then(BTreeMap::new);
+    if args.only_violations && max.is_none() {
+        bail!("--only-violations needs a maximum, from --max or --compare-to-runtime");
+    }
+
+    let changed = args
+        .changed_since
+        .as_deref()
+        .map(|base| git::changed_files(&std::env::current_dir()?, base))
+        .transpose()?;
+
+    if args.merge {
+        let reports = args
+            .files
+            .iter()
+            .map(|path| merge::read_report(path))
+            .collect::<Result<Vec<_>, _>>()?;
+        report = merge::merge(reports.into_iter().flatten());
+        for file in &report {
+            if let Some(histogram) = &mut histogram {
+                *histogram.entry(*file.version).or_default() += 1;
+            }
+            if let Some(counts) = &mut counts {
+                count_versions(counts, file, args.list);
+            }
+            policies.check(&file.path, &file.version);
+        }
+        highest = report
+            .iter()
+            .map(|file| file.version.clone())
+            .max()
+            .unwrap_or(highest);
+        too_high = report
+            .iter()
+            .filter(|file| max.is_some_and(|max| *file.version > max))
+            .map(|file| (file.path.clone(), file.version.clone()))
+            .collect();
+        // reports only know the highest version of a file, which has to do
+        too_low = report
+            .iter()
+            .filter(|file| args.min.is_some_and(|min| *file.version < min))
+            .map(|file| (file.path.clone(), file.version.clone()))
+            .collect();
+    }
+    // with --merge, the paths are reports and not anything to scan
+    let inputs = if args.merge {
+        Vec::new()
+    } else {
+        walk::expand(args.files, args.recursive)?
+    };
+
+    let ignore_file = IgnoreFile::find(&std::env::current_dir()?)?;
+    let files = inputs.into_iter().filter(|file| {
+        let ignored_by = args.ignore_paths.iter().find(|glob| glob.is_match(file));
+        if let Some(glob) = ignored_by {
+            debug!("Ignoring {file}, it matches --ignore-path {glob}");
+            return false;
+        }
+        // comes second, so nothing in there can bring back what --ignore-path is excluding
+        if ignore_file
+            .as_ref()
+            .is_some_and(|ignore| ignore.is_ignored(file))
+        {
+            debug!("Ignoring {file}, because of {IGNORE_FILE}");
+            return false;
+        }
+        true
+    });
+    let files = files.filter(|file| match &changed {
+        // git has no idea what is piped in or downloaded
+        Some(changed) if is_local(file) && !git::is_changed(file, changed) => {
+            debug!("Skipping {file}, git says it didn't change");
+            false
+        }
+        _ => true,
+    });
+
+    if args.names_only {
+        for file in files {
+            for name in in_file(
+                names_only(&file, args.timeout, args.include_meta_inf),
+                &file,
+            )? {
+                println!("{name}");
+            }
+        }
+        return Ok(());
+    }
+    if args.watch {
+        return watch::watch(files.collect(), &options, max);
+    }
+    let files: Vec<String> = files.collect();
+    // a bar in the middle of output that is meant for a machine (or a file) would only get in the way, and so would
+    // the bars of several archives at the same time
+    if args.progress
+        && formats_in_use == [OutputFormat::Text]
+        && (args.jobs == 1 || files.len() == 1)
+        && io::stdout().is_terminal()
+        && io::stderr().is_terminal()
+    {
+        progress::enable();
+    }
+
+    let dedupe = args.dedupe_by_content.then(Dedupe::new);
+    let cache = match &cache_dir {
+        Some(dir) if !args.no_cache => Some(Cache::new(dir.clone(), &options)),
+        _ => None,
+    };
+    // files that are skipped instead of ending the run, by why they were skipped
+    let mut timed_out = Vec::new();
+    // how many files went into `errors`, which might have been streamed already
+    let mut failed = 0;
+    // what the first of them failed for, to exit with
+    let mut failure_code = None;
+    let mut permission_denied = Vec::new();
+    // runs on threads of their own with --jobs, everything else happens in the order of the files
+    let scan_one = |file: &String| -> anyhow::Result<ScanResult> {
+        if let Some(count) = args.head_bytes {
+            // only there to help figure out what a file is, so it failing isn't a reason to stop
+            match head_bytes(file, count) {
+                Ok(dump) => eprintln!("First bytes of {file}:\n{dump}"),
+                Err(e) => eprintln!("Failed to read the first bytes of {file}: {e}"),
+            }
+        }
+        let extension = Path::new(file).extension().and_then(|s| s.to_str());
+        let scan = || match args.scan_timeout {
+            // a single class can't take long enough to be worth a thread
+            Some(timeout) if extension != Some("class") => {
+                let (file, options) = (file.clone(), options.clone());
+                with_timeout(timeout, move || scan_file(&file, &options))?
+            }
+            _ => scan_file(file, &options),
+        };
+        // hashing (or caching) a class file costs as much as just reading it
+        let archive = extension != Some("class") && is_local(file);
+        let scan = || match &cache {
+            Some(cache) if archive => cache.scan(file, scan),
+            _ => scan(),
+        };
+        match &dedupe {
+            Some(dedupe) if archive => dedupe.scan(file, scan),
+            _ => scan(),
+        }
+    };
+    thread::scope(|scope| -> anyhow::Result<()> {
+        let scanned = parallel::map_in_order(scope, &files, args.jobs, &scan_one);
+        for (file, result) in files.iter().zip(scanned) {
+            let file = file.clone();
+            let path = Path::new(&file);
+            let extension = path.extension().and_then(|s| s.to_str());
+            let ScanResult {
+                version,
+                lowest,
+                classes,
+                with_debug_info,
+                preview,
+                histogram: classes_per_major,
+                // already warned about
+                skipped_entries: _,
+            } = match (result, &mut zero_class_jars) {
+                (Err(e), _) if e.is::<TimedOut>() => {
+                    warn!("{file}: {e}, skipping it");
+                    timed_out.push(file);
+                    continue;
+                }
+                (Err(e), _) if is_permission_denied(&e) => {
+                    warn!("{file}: permission denied, skipping it");
+                    permission_denied.push(file);
+                    continue;
+                }
+                (Err(e), Some(jars))
+                    if matches!(
+                        e.downcast_ref::<ExtractedJarError>(),
+                        Some(ExtractedJarError::NoClassFiles)
+                    ) =>
+                {
+                    debug!("{file} does not contain any class files");
+                    jars.push(file);
+                    continue;
+                }
+                (Err(e), _) if collect_errors => {
+                    let error = format!("{e:#}");
+                    warn!("Failed to scan {file}: {error}");
+                    failure_code.get_or_insert(ExitCode::of(&e));
+                    let error = FileError { path: file, error };
+                    if args.keep_going {
+                        failures.push(error.clone());
+                    }
+                    match &mut stream {
+                        Some(stream) => stream.push(&error)?,
+                        None => errors.push(error),
+                    }
+                    failed += 1;
+                    continue;
+                }
+                (result, _) => in_file(result, &file)?,
+            };
+            let class_count = classes_per_major.values().sum();
+            if let Some(count) = with_debug_info {
+                info!("{file}: {count} class(es) with debug info");
+            }
+            // a class file is just the one version
+            if args.range && extension != Some("class") {
+                info!("{file}: min {lowest}, max {version}");
+            }
+            if args.list {
+                if classes.is_empty() {
+                    // only archives hand out their classes, a class file is just the one with its version
+                    for major in classes_per_major.keys() {
+                        info!("{file} {major}");
+                    }
+                } else {
+                    let mut listing: Vec<_> = classes.iter().collect();
+                    listing.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    for (name, version) in listing {
+                        info!("{name} {}", version.class_major());
+                    }
+                }
+            }
+            if args.histogram && extension != Some("class") {
+                info!("{file} by major version:");
+                for (major, count) in classes_per_major {
+                    let version = JavaVersion::from(JavaClass { major, minor: 0 });
+                    info!("  {major} ({version}): {count} class(es)");
+                }
+            }
+            if args.fail_on_preview {
+                preview_classes.extend(preview.into_iter().map(|name| {
+                    if name.is_empty() {
+                        file.clone()
+                    } else {
+                        format!("{file}!/{name}")
+                    }
+                }));
+            }
+            if let Some(max) = max {
+                trace!("max is set; checking");
+                if *version > max {
+                    trace!("version version {version} is higher than {max}!");
+                    too_high.insert((file.clone(), version.clone()));
+                }
+            }
+            if let Some(min) = args.min
+                && *lowest < min
+            {
+                trace!("version {lowest} is lower than {min}!");
+                too_low.insert((file.clone(), lowest));
+            }
+            if args.version_only {
+                if args.raw_major {
+                    println!("{}", version.class_major());
+                } else {
+                    println!("{}", *version);
+                }
+            }
+            let real_path = (args.resolve_symlinks && is_local(&file))
+                .then(|| in_file(real_path(&file).map_err(Into::into), &file))
+                .transpose()?
+                .flatten();
+            let file_report = FileReport {
+                path: file,
+                version: version.clone(),
+                classes,
+                with_debug_info,
+                real_path,
+                class_count: Some(class_count),
+            };
+            if let Some(histogram) = &mut histogram {
+                *histogram.entry(*file_report.version).or_default() += 1;
+            }
+            if let Some(counts) = &mut counts {
+                count_versions(counts, &file_report, args.list);
+            }
+            policies.check(&file_report.path, &file_report.version);
+            if file_report.version > highest {
+                highest = file_report.version.clone();
+            }
+            match &mut stream {
+                // files that are fine don't make it into the output, the histogram already counted them
+                Some(_)
+                    if args.only_violations
+                        && file_report
+                            .version
+                            .over_max(max.unwrap_or(u16::MAX))
+                            .is_none() => {}
+                Some(stream) => stream.push(&file_report)?,
+                None => report.push(file_report),
+            }
+            if args.fail_fast && !too_high.is_empty() {
+                debug!("Not scanning the rest, because of --fail-fast");
+                break;
+            }
+        }
+        Ok(())
+    })?;
+    if let Some(cache) = cache.as_ref().filter(|cache| cache.hits() > 0) {
+        info!(
+            "Used the cached result for {} archive(s) that didn't change since the last run",
+            cache.hits()
+        );
+    }
+    if let Some(dedupe) = dedupe {
+        info!(
+            "Collapsed {} file(s) with the same content as another one",
+            dedupe.duplicates()
+        );
+    }
+    let text = TextOptions {
+        max,
+        group_by_version: args.group_by_version,
+        limit_per_version: args.limit_per_version,
+        table: args.summary,
+    };
+    if let Some(order) = args.sort {
+        sort_report(&mut report, order, max);
+    }
+    let zero_class_jars = zero_class_jars.as_deref();
+    let summary = histogram.and_then(Summary::from_histogram);
+    let context = OutputContext {
+        text,
+        merged: args.merge,
+        zero_class_jars,
+        only_violations: summary.as_ref().filter(|_| args.only_violations),
+        aggregate: args.aggregate,
+        errors: &errors,
+    };
+    if let Some(path) = &args.batch_summary_json {
+        debug!("Writing summary to {}", path.display());
+        let summary = render_batch_summary(summary.as_ref(), max).to_string();
+        write_atomically(path, &summary)
+            .with_context(|| format!("Failed to write summary to {}", path.display()))?;
+    }
+    if let Some(stream) = stream {
+        let mut trailing = Map::new();
+        if let Some(summary) = context.only_violations {
+            trailing.insert("summary".to_owned(), json!(summary));
+        }
+        trailing.extend(zero_class_jars.map(zero_class_jars_field));
+        stream.finish(&trailing)?;
+    } else if args.reports.is_empty() {
+        match args.format {
+            // without being asked for it, there is no text report
+            OutputFormat::Text
+                if !args.merge
+                    && args.sort.is_none()
+                    && !args.group_by_version
+                    && !args.summary
+                    && args.aggregate == Aggregate::List =>
+            {
+                if let Some(jars) = zero_class_jars {
+                    info!("{}", render_zero_class_jars(jars).trim_end());
+                }
+            }
+            OutputFormat::Text => info!(
+                "{}",
+                render_output(OutputFormat::Text, &report, &context).trim_end()
+            ),
+            format => println!("{}", render_output(format, &report, &context)),
+        }
+    }
+    for target in &args.reports {
+        let output = render_output(target.format, &report, &context);
+        match &target.destination {
+            Destination::Stdout => println!("{}", output.trim_end()),
+            Destination::File(path) => {
+                debug!("Writing {:?} report to {}", target.format, path.display());
+                fs::write(path, output + "\n")
+                    .with_context(|| format!("Failed to write report to {}", path.display()))?
+            }
+        }
+    }
+    if let Some(counts) = &counts {
+        info!("{}", render_count(counts, args.list));
+    }
+    if args.recommend_jdk && *highest > 0 {
+        info!("{}", highest.jdk_recommendation(args.lts));
+    }
+    if let Some(verify) = args.verify {
+        verify.check(&report)?;
+        info!("All {} file(s) are {}", report.len(), verify.expected());
+    }
+    if !policies.is_empty() {
+        info!("{}", policies.render().trim_end());
+        policies.result()?;
+    }
+    let skipped = [
+        ("scanning timed out", timed_out),
+        ("permission denied", permission_denied),
+    ];
+    for (reason, files) in &skipped {
+        if !files.is_empty() {
+            warn!(
+                "Skipped {} file(s), {reason}: {}",
+                files.len(),
+                files.join(", ")
+            );
+        }
+    }
+    if args.fail_on_error && skipped.iter().any(|(_, files)| !files.is_empty()) {
+        bail!("Some files were skipped, failing because of --fail-on-error");
+    }
+    let mut violations = limits.violations(too_high, too_low);
+    // a file that is too new is the more interesting reason to fail, it's what this is for after all
+    let code = if violations.is_empty() && preview_classes.is_empty() {
+        failure_code.unwrap_or(ExitCode::Failure)
+    } else {
+        ExitCode::Version
+    };
+    // the files that could be scanned are still checked, the ones that couldn't come first
+    if failed > 0 {
+        let mut message = format!("{failed} file(s) couldn't be scanned");
+        for FileError { path, error } in &failures {
+            message.push_str(&format!("\n  {path}: {error}"));
+        }
+        violations.insert(0, message);
+    }
+    if !preview_classes.is_empty() {
+        violations.push(format!(
+            "Found {} class(es) compiled with --enable-preview:\n  {}",
+            preview_classes.len(),
+            preview_classes.join("\n  ")
+        ));
+    }
+    if !violations.is_empty() {
+        return Err(Failed {
+            message: violations.join("\n"),
+            code,
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{archive::EntryVisitor, sevenz::tests::sevenz_with};
+    use std::{
+        collections::HashSet,
+        io::{Cursor, Write},
+    };
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    pub(crate) fn java_class(major: u16) -> JavaClass {
+        JavaClass { major, minor: 0 }
+    }
+
+    pub(crate) fn classes_of(extracted: &ExtractedJar) -> Vec<JavaClass> {
+        extracted
+            .classfiles
+            .iter()
+            .map(|(_, class)| class.clone())
+            .collect()
+    }
+
+    /// A directory of its own for a test, which is removed again once the test is done, no matter how it went
+    pub(crate) struct TempDir(std::path::PathBuf);
+
+    impl TempDir {
+        /// Starts out empty, even if an earlier run of the same test left something behind
+        pub(crate) fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "java-classfile-version-{name}-{}",
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Deref for TempDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl AsRef<Path> for TempDir {
+        fn as_ref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// A report of files with just a path and a version, without anything that was found along the way
+    pub(crate) fn report_of(versions: &[(&str, u16)]) -> Vec<FileReport> {
+        versions
+            .iter()
+            .map(|(path, version)| FileReport {
+                path: path.to_string(),
+                version: JavaVersion(*version),
+                classes: Vec::new(),
+                with_debug_info: None,
+                real_path: None,
+                class_count: None,
+            })
+            .collect()
+    }
+
+    /// Builds a zip archive in memory containing the given entries
+    pub(crate) fn jar_with(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut jar = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in entries {
+            jar.start_file(*name, SimpleFileOptions::default()).unwrap();
+            jar.write_all(data).unwrap();
+        }
+        jar.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_java_version_from_java_class() {
+        let java_class = java_class(52);
+        let java_version: JavaVersion = java_class.into();
+        assert_eq!(*java_version, 8);
+    }
+
+    #[test]
+    fn test_java_version_from_iter() {
+        let classes = vec![java_class(50), java_class(52), java_class(51)];
+        let version: JavaVersion = JavaVersion::from_iter(classes);
+        assert_eq!(*version, 8);
+    }
+
+    #[test]
+    fn test_java_version_from_empty_iter() {
+        let classes: Vec<JavaClass> = vec![];
+        let version: JavaVersion = JavaVersion::from_iter(classes);
+        assert_eq!(*version, 0);
+    }
+
+    #[test]
+    fn test_java_version_range() {
+        let classes = vec![java_class(55), java_class(52), java_class(61)];
+        assert_eq!(
+            JavaVersion::version_range(classes),
+            (JavaVersion(8), JavaVersion(17))
+        );
+        assert_eq!(
+            JavaVersion::version_range(vec![]),
+            (JavaVersion(0), JavaVersion(0))
+        );
+    }
+
+    #[test]
+    fn test_java_version_cmp_class_major() {
+        let java8 = JavaVersion(8);
+        assert_eq!(java8.cmp_class_major(52), Ordering::Equal);
+        assert_eq!(java8.cmp_class_major(53), Ordering::Less);
+        assert_eq!(java8.cmp_class_major(51), Ordering::Greater);
+        // comparing the feature number against the class major directly would get this wrong
+        assert_eq!(JavaVersion(52).cmp_class_major(52), Ordering::Greater);
+        assert_eq!(
+            JavaVersion(u16::MAX).cmp_class_major(u16::MAX),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_java_version_class_major() {
+        assert_eq!(JavaVersion(8).class_major(), 52);
+        assert_eq!(JavaVersion::from(java_class(65)).class_major(), 65);
+        assert_eq!(JavaVersion(u16::MAX).class_major(), 65579);
+    }
+
+    #[test]
+    fn test_java_version_below_offset() {
+        let version = |major| JavaVersion::from(JavaClass { major, minor: 0 });
+        assert_eq!(version(40), JavaVersion(0));
+        assert_eq!(version(JAVA_VERSION_OFFSET), JavaVersion(0));
+        assert_eq!(version(JAVA_VERSION_OFFSET + 1), JavaVersion(1));
+    }
+
+    #[test]
+    fn test_java_version_from_garbage() {
+        // what a truncated or otherwise broken file would come out as, if nothing checked the major version first
+        let garbage = [10, 0].map(|major| JavaClass { major, minor: 0 });
+        assert_eq!(JavaVersion::from(garbage[0].clone()), JavaVersion(0));
+        assert_eq!(JavaVersion::from_iter(garbage.clone()), JavaVersion(0));
+        let with_real_class = garbage.iter().cloned().chain([java_class(52)]);
+        assert_eq!(
+            JavaVersion::version_range(with_real_class),
+            (JavaVersion(0), JavaVersion(8))
+        );
+        assert_eq!(
+            JavaVersion::from(garbage[0].clone()).release(),
+            JavaRelease::Unknown(0)
+        );
+    }
+
+    #[test]
+    fn test_java_version_over_max() {
+        assert_eq!(JavaVersion(21).over_max(17), Some(4));
+        assert_eq!(JavaVersion(17).over_max(17), None);
+        assert_eq!(JavaVersion(8).over_max(17), None);
+    }
+
+    #[test]
+    fn test_java_version_remediation_hint() {
+        assert_eq!(
+            JavaVersion(21).remediation_hint(17),
+            "compiled for Java 21; rebuild with `--release 17` or lower"
+        );
+        assert_eq!(
+            JavaVersion(11).remediation_hint(7),
+            "compiled for Java 11; rebuild with `-source 7 -target 7` on a JDK that still supports it"
+        );
+    }
+
+    #[test]
+    fn test_java_version_lts() {
+        let lts: Vec<_> = (1..=30).filter(|&v| JavaVersion(v).is_lts()).collect();
+        assert_eq!(lts, vec![8, 11, 17, 21, 25, 29]);
+        assert_eq!(JavaVersion(5).next_lts(), JavaVersion(8));
+        assert_eq!(JavaVersion(12).next_lts(), JavaVersion(17));
+        assert_eq!(JavaVersion(21).next_lts(), JavaVersion(21));
+    }
+
+    #[test]
+    fn test_java_version_jdk_recommendation() {
+        // the highest version of a mixed bag of classes is what counts
+        let highest: JavaVersion = [java_class(52), java_class(61), java_class(55)]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            highest.jdk_recommendation(false),
+            "These artifacts require JDK 17 or newer"
+        );
+        assert_eq!(
+            highest.jdk_recommendation(true),
+            "These artifacts require JDK 17 or newer, which is an LTS release"
+        );
+        assert_eq!(
+            JavaVersion(22).jdk_recommendation(true),
+            "These artifacts require JDK 22 or newer, the closest LTS release is JDK 25"
+        );
+    }
+
+    #[test]
+    fn test_java_version_from_str() {
+        assert_eq!("17".parse(), Ok(JavaVersion(17)));
+        assert_eq!("1.8".parse(), Ok(JavaVersion(8)));
+        assert_eq!("30".parse(), Ok(JavaVersion(30)));
+
+        let error = |input: &str| input.parse::<JavaVersion>().unwrap_err().to_string();
+        assert_eq!(
+            error("oops"),
+            "`oops` is not a Java version, expected something like `17` or `1.8`"
+        );
+        assert_eq!(
+            error("jdk17"),
+            "`jdk17` is not a Java version, expected something like `17` or `1.8`. Did you mean 17?"
+        );
+        assert_eq!(
+            error("1.17"),
+            "`1.17` is not a Java version, expected something like `17` or `1.8`. Did you mean 17?"
+        );
+        assert_eq!(
+            error("99999999"),
+            format!(
+                "`99999999` is not a Java version, expected something like `17` or `1.8`. Did you mean {LATEST_KNOWN_VERSION}?"
+            )
+        );
+    }
+
+    #[test]
+    fn test_java_version_display() {
+        let version = JavaVersion(11);
+        let formatted = format!("{}", version);
+        assert_eq!(formatted, "Java 11");
+        // no "Java 1" for the oldest classes there are
+        let legacy = |major| JavaVersion::from(java_class(major)).to_string();
+        assert_eq!(legacy(45), "Java 1.1");
+        assert_eq!(JavaVersion(0).to_string(), "no classes");
+    }
+
+    #[test]
+    fn test_java_version_marketing_name() {
+        let name = |major| JavaVersion::from(java_class(major)).marketing_name();
+        assert_eq!(name(45), "1.1");
+        assert_eq!(name(49), "1.5");
+        assert_eq!(name(52), "1.8");
+        assert_eq!(name(53), "9");
+        assert_eq!(name(65), "21");
+    }
+
+    #[test]
+    fn test_java_class_new_valid() {
+        let class_bytes = vec![
+            202, 254, 186, 190, // CAFEBABE magic
+            0, 0, // minor version
+            0, 52, // major version (Java 8)
+        ];
+        let cursor = Cursor::new(class_bytes);
+        let result = JavaClass::new(cursor);
+
+        assert!(result.is_ok());
+        let class = result.unwrap();
+        assert_eq!((class.major, class.minor), (52, 0));
+    }
+
+    #[test]
+    fn test_java_class_implausible_version() {
+        let class = |major: u16| {
+            let [major_hi, major_lo] = major.to_be_bytes();
+            [202, 254, 186, 190, 0, 0, major_hi, major_lo]
+        };
+        for major in [0, 3, 44, 9999] {
+            assert!(matches!(
+                JavaClass::new(&class(major)[..]),
+                Err(JavaClassError::ImplausibleVersion(m)) if m == major
+            ));
+        }
+        assert!(JavaClass::new(&class(45)[..]).is_ok());
+        assert!(JavaClass::new(&class(DEFAULT_MAX_CLASS_MAJOR)[..]).is_ok());
+        assert!(JavaClass::with_max_major(&class(70)[..], 69).is_err());
+    }
+
+    #[test]
+    fn test_java_class_is_preview() {
+        let class = |minor: u16, major: u16| {
+            let [minor_hi, minor_lo] = minor.to_be_bytes();
+            JavaClass::new(&[202, 254, 186, 190, minor_hi, minor_lo, 0, major as u8][..]).unwrap()
+        };
+        let preview = class(PREVIEW_MINOR_VERSION, 65);
+        assert_eq!((preview.major, preview.minor), (65, 0xFFFF));
+        assert!(preview.is_preview());
+        assert!(!class(0, 65).is_preview());
+        // preview features only exist since 12
+        assert!(!class(PREVIEW_MINOR_VERSION, 52).is_preview());
+    }
+
+    #[test]
+    fn test_java_class_new_insufficient_bytes() {
+        let class_bytes = vec![202, 254, 186, 190, 0]; // Only 5 bytes
+        let cursor = Cursor::new(class_bytes);
+        let result = JavaClass::new(cursor);
+
+        assert!(matches!(result, Err(JavaClassError::InsufficientBytes(5))));
+    }
+
+    #[test]
+    fn test_java_class_new_short_reads() {
+        /// Hands out a single byte per read, like a slow pipe
+        struct Trickle<'a>(&'a [u8]);
+
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let Some((first, rest)) = self.0.split_first() else {
+                    return Ok(0);
+                };
+                buf[0] = *first;
+                self.0 = rest;
+                Ok(1)
+            }
+        }
+
+        let class = JavaClass::new(Trickle(&[202, 254, 186, 190, 0, 0, 0, 61])).unwrap();
+        assert_eq!(class, java_class(61));
+        assert!(matches!(
+            JavaClass::new(Trickle(&[202, 254, 186])),
+            Err(JavaClassError::InsufficientBytes(3))
+        ));
+    }
+
+    #[test]
+    fn test_java_class_new_invalid_magic() {
+        let class_bytes = vec![
+            1, 2, 3, 4, // Invalid magic
+            0, 0, // minor version
+            0, 52, // major version
+        ];
+        let cursor = Cursor::new(class_bytes);
+        let result = JavaClass::new(cursor);
+
+        assert!(matches!(result, Err(JavaClassError::NotAClassFile)));
+    }
+
+    #[test]
+    fn test_write_atomically() {
+        let dir = TempDir::new("atomic");
+        let path = dir.join("summary.json");
+        fs::write(&path, "old").unwrap();
+        write_atomically(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        // nothing left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_limits_violations() {
+        let limits = Limits {
+            max: Some(17),
+            exclusive: false,
+            min: Some(11),
+            hint: false,
+        };
+        let files = |files: &[(&str, u16)]| {
+            files
+                .iter()
+                .map(|&(path, v)| (path.to_owned(), JavaVersion(v)))
+                .collect()
+        };
+        assert!(limits.violations(files(&[]), files(&[])).is_empty());
+        assert_eq!(
+            limits.violations(
+                files(&[("lib/b.jar", 21), ("app.jar", 18), ("lib/b.jar", 21)]),
+                files(&[("lib/old.jar", 8), ("Legacy.class", 6)])
+            ),
+            vec![
+                "Found class(es) higher than the given maximum of 17!\n  app.jar: Java 18\n  lib/b.jar: Java 21",
+                "Found class(es) lower than the given minimum of 11!\n  Legacy.class: Java 1.6\n  lib/old.jar: Java 1.8",
+            ]
+        );
+        let only_min = Limits {
+            max: None,
+            ..limits
+        };
+        assert_eq!(
+            only_min.violations(files(&[]), files(&[("app.jar", 8)])),
+            vec!["Found class(es) lower than the given minimum of 11!\n  app.jar: Java 1.8"]
+        );
+    }
+
+    #[test]
+    fn test_limits_max_exclusive() {
+        let at_max = JavaVersion(17);
+        let inclusive = Limits {
+            max: Some(17),
+            exclusive: false,
+            min: None,
+            hint: false,
+        };
+        assert_eq!(inclusive.allowed(), Some(17));
+        assert_eq!(at_max.over_max(inclusive.allowed().unwrap()), None);
+
+        let exclusive = Limits {
+            exclusive: true,
+            ..inclusive
+        };
+        assert_eq!(exclusive.allowed(), Some(16));
+        assert_eq!(at_max.over_max(exclusive.allowed().unwrap()), Some(1));
+        assert_eq!(JavaVersion(16).over_max(exclusive.allowed().unwrap()), None);
+        let too_high = BTreeSet::from([("app.jar".to_owned(), at_max)]);
+        assert_eq!(
+            exclusive.violations(too_high.clone(), BTreeSet::new()),
+            vec!["Found class(es) at or above the given maximum of 17!\n  app.jar: Java 17"]
+        );
+        let with_hint = Limits {
+            hint: true,
+            ..exclusive
+        };
+        assert!(with_hint.violations(too_high, BTreeSet::new())[0].contains("--release 16"));
+    }
+
+    #[test]
+    fn test_is_permission_denied() {
+        let denied = || io::Error::from(io::ErrorKind::PermissionDenied);
+        assert!(is_permission_denied(
+            &ExtractedJarError::IO(denied()).into()
+        ));
+        assert!(is_permission_denied(&JavaClassError::Read(denied()).into()));
+        assert!(is_permission_denied(&denied().into()));
+        assert!(!is_permission_denied(
+            &ExtractedJarError::IO(io::Error::from(io::ErrorKind::NotFound)).into()
+        ));
+        assert!(!is_permission_denied(&ExtractedJarError::NotAJar.into()));
+    }
+
+    #[test]
+    fn test_scan_stdin() {
+        let class = [202, 254, 186, 190, 0, 0, 0, 61];
+        let options = ScanOptions::default();
+        let scan = |data: Vec<u8>| scan_in_memory(STDIN, Cursor::new(data), &options);
+        assert_eq!(scan(class.to_vec()).unwrap().version, JavaVersion(17));
+        let jar = jar_with(&[
+            ("Foo.class", &class),
+            ("Bar.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+        ]);
+        let result = scan(jar).unwrap();
+        assert_eq!(
+            (result.version, result.lowest),
+            (JavaVersion(17), JavaVersion(8))
+        );
+        assert!(scan(b"not a class".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_compression() {
+        let mut jar = jar_with(&[
+            ("Good.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+            ("Odd.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+        ]);
+        // pretend Odd.class is compressed with something nobody has heard of, in both of its headers
+        let odd = |signature: &[u8], name_at: usize| {
+            (0..jar.len())
+                .find(|&i| {
+                    jar[i..].starts_with(signature) && jar[i + name_at..].starts_with(b"Odd.class")
+                })
+                .unwrap()
+        };
+        let (local, central) = (odd(b"PK\x03\x04", 30), odd(b"PK\x01\x02", 46));
+        jar[local + 8..local + 10].copy_from_slice(&77u16.to_le_bytes());
+        jar[central + 10..central + 12].copy_from_slice(&77u16.to_le_bytes());
+
+        let extracted =
+            ExtractedJar::from_reader(Cursor::new(jar), &ScanOptions::default()).unwrap();
+        assert_eq!(classes_of(&extracted), [java_class(52)]);
+        assert_eq!(extracted.skipped, 1);
+    }
+
+    #[test]
+    fn test_extracted_jar_error_java_class() {
+        let e = ExtractedJarError::from(JavaClassError::NotAClassFile);
+        assert_eq!(e.to_string(), "Invalid class file");
+        assert_eq!(
+            format!("{:#}", anyhow::Error::from(e)),
+            "Invalid class file: Not a java class"
+        );
+    }
+
+    #[test]
+    fn test_names_only_by_content() {
+        let dir = TempDir::new("names-only");
+        let class = [202, 254, 186, 190, 0, 0, 0, 61];
+        // both named like what the other one is
+        let renamed_class = dir.join("Foo.jar");
+        fs::write(&renamed_class, class).unwrap();
+        let renamed_jar = dir.join("app.class");
+        fs::write(&renamed_jar, jar_with(&[("Foo.class", &class)])).unwrap();
+        let (renamed_class, renamed_jar) = (
+            renamed_class.to_str().unwrap(),
+            renamed_jar.to_str().unwrap(),
+        );
+        assert_eq!(
+            names_only(renamed_class, None, false).unwrap(),
+            [renamed_class]
+        );
+        assert_eq!(
+            names_only(renamed_jar, None, false).unwrap(),
+            [format!("{renamed_jar}!/Foo.class")]
+        );
+    }
+
+    /// The highest version of a jar with a Java 17 and a Java 21 class, scanned like `args` (with `--max 11`) would
+    fn highest_with_max(args: &[&str]) -> JavaVersion {
+        let dir = TempDir::new(&format!("highest-with-max{}", args.concat()));
+        let jar = dir.join("app.jar");
+        fs::write(
+            &jar,
+            jar_with(&[
+                ("A.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+                ("B.class", &[202, 254, 186, 190, 0, 0, 0, 65]),
+            ]),
+        )
+        .unwrap();
+        let jar = jar.to_str().unwrap();
+        let args = ["java-classfile-version", "--no-config", "--max", "11"]
+            .into_iter()
+            .chain(args.iter().copied())
+            .chain([jar]);
+        let args = Cli::from_args(args).unwrap();
+        let options = ScanOptions {
+            stop_above: args.max.filter(|_| !needs_inventory(&args)),
+            ..ScanOptions::default()
+        };
+        process_archive(jar, &options).unwrap().version
+    }
+
+    #[test]
+    fn test_needs_inventory() {
+        // only needs to know that something is too high
+        assert_eq!(highest_with_max(&[]), JavaVersion(17));
+        assert_eq!(highest_with_max(&["--version-only"]), JavaVersion(21));
+        assert_eq!(highest_with_max(&["--recommend-jdk"]), JavaVersion(21));
+        assert_eq!(
+            highest_with_max(&["--batch-summary-json", "/dev/null"]),
+            JavaVersion(21)
+        );
+        assert_eq!(highest_with_max(&["--check-stripped"]), JavaVersion(21));
+    }
+
+    #[test]
+    fn test_in_file() {
+        let dir = TempDir::new("in-file");
+        let class = dir.join("Broken.class");
+        // the right magic, but nothing after it
+        fs::write(&class, [202, 254, 186, 190]).unwrap();
+        let class = class.to_str().unwrap();
+        let e = in_file(scan_file(class, &ScanOptions::default()), class).unwrap_err();
+        assert!(format!("{e:#}").starts_with(&format!("while processing {class}: ")));
+        // still fails for what it failed for
+        assert_eq!(ExitCode::of(&e), ExitCode::Format);
+    }
+
+    #[test]
+    fn test_scan_file_strict() {
+        let dir = TempDir::new("strict");
+        let class = [202, 254, 186, 190, 0, 0, 0, 61];
+        let file = |name: &str, content: &[u8]| {
+            let path = dir.join(name);
+            fs::write(&path, content).unwrap();
+            path.display().to_string()
+        };
+        let (named, unnamed) = (file("Foo.class", &class), file("notes.txt", &class));
+        let renamed = file("app.jar", &class);
+        let options = ScanOptions {
+            strict: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            scan_file(&named, &options).unwrap().version,
+            JavaVersion(17)
+        );
+        // without --strict, the content is all that counts
+        assert!(scan_file(&unnamed, &ScanOptions::default()).is_ok());
+        assert_eq!(
+            scan_file(&unnamed, &options).unwrap_err().to_string(),
+            format!(
+                "{unnamed} isn't named like a class file or an archive, which --strict requires"
+            )
+        );
+        let e = scan_file(&renamed, &options).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            format!(
+                "{renamed} is named like a zip archive, but is a class, which --strict doesn't allow"
+            )
+        );
+        assert_eq!(ExitCode::of(&e), ExitCode::Format);
+    }
+
+    #[test]
+    fn test_scan_file_by_content() {
+        let dir = TempDir::new("by-content");
+        let class = [202, 254, 186, 190, 0, 0, 0, 61];
+        let jar = jar_with(&[("Foo.class", &[202, 254, 186, 190, 0, 0, 0, 52])]);
+        let file = |name: &str, content: &[u8]| {
+            let path = dir.join(name);
+            fs::write(&path, content).unwrap();
+            path.display().to_string()
+        };
+        let (jar_as_class, class_as_jar) = (file("Foo.class", &jar), file("app.jar", &class));
+        let sevenz_as_jar = file("dist.jar", &sevenz_with(&[("lib/app.jar", &jar)]));
+        let junk = file("junk.jar", b"definitely not a jar");
+        let (empty_class, empty_jar) = (file("Empty.class", b""), file("empty.jar", b""));
+
+        assert_eq!(detect_format(&jar_as_class).unwrap(), Format::Zip);
+        assert_eq!(detect_format(&class_as_jar).unwrap(), Format::Class);
+        assert_eq!(detect_format(&sevenz_as_jar).unwrap(), Format::SevenZ);
+        let options = ScanOptions::default();
+        assert_eq!(
+            scan_file(&sevenz_as_jar, &options).unwrap().version,
+            JavaVersion(8)
+        );
+        assert_eq!(
+            scan_file(&jar_as_class, &options).unwrap().version,
+            JavaVersion(8)
+        );
+        assert_eq!(
+            scan_file(&class_as_jar, &options).unwrap().version,
+            JavaVersion(17)
+        );
+        assert!(
+            scan_file(&junk, &options)
+                .unwrap_err()
+                .is::<UnknownFormat>()
+        );
+        for empty in [empty_class, empty_jar] {
+            assert_eq!(
+                scan_file(&empty, &options).unwrap_err().to_string(),
+                format!("{empty} is empty")
+            );
+        }
+        let stdin = scan_in_memory(STDIN, Cursor::new(Vec::new()), &options).unwrap_err();
+        assert_eq!(stdin.to_string(), "stdin is empty");
+    }
+
+    #[test]
+    fn test_scan_result_preview() {
+        let preview = [202, 254, 186, 190, 255, 255, 0, 65];
+        let options = ScanOptions::default();
+        let scan = |data: Vec<u8>| {
+            scan_in_memory(STDIN, Cursor::new(data), &options)
+                .unwrap()
+                .preview
+        };
+        assert_eq!(scan(preview.to_vec()), [""]);
+        assert!(scan(vec![202, 254, 186, 190, 0, 0, 0, 65]).is_empty());
+        let jar = jar_with(&[
+            ("app/Main.class", &[202, 254, 186, 190, 0, 0, 0, 65]),
+            ("app/New.class", &preview),
+        ]);
+        assert_eq!(scan(jar), ["app/New.class"]);
+    }
+
+    #[test]
+    fn test_head_bytes() {
+        let dir = TempDir::new("head");
+        let path = dir.join("Foo.class");
+        fs::write(&path, [202, 254, 186, 190, 0, 0, 0, 61, b'A', b'B']).unwrap();
+        let path = path.to_str().unwrap();
+        assert_eq!(
+            head_bytes(path, 8).unwrap(),
+            "00000000  ca fe ba be 00 00 00 3d                          |.......=|"
+        );
+        assert_eq!(
+            head_bytes(path, 100).unwrap(),
+            "00000000  ca fe ba be 00 00 00 3d 41 42                    |.......=AB|"
+        );
+    }
+
+    #[test]
+    fn test_strip_entry_prefix() {
+        let pattern = Regex::new("BOOT-INF/classes/").unwrap();
+        assert_eq!(
+            strip_entry_prefix("BOOT-INF/classes/com/example/App.class", &pattern),
+            "com/example/App.class"
+        );
+        assert_eq!(
+            strip_entry_prefix("app.jar!/BOOT-INF/classes/com/example/App.class", &pattern),
+            "app.jar!/com/example/App.class"
+        );
+        // only ever the start
+        assert_eq!(
+            strip_entry_prefix("shaded/BOOT-INF/classes/Foo.class", &pattern),
+            "shaded/BOOT-INF/classes/Foo.class"
+        );
+        let pattern = Regex::new(r"(?:BOOT|WEB)-INF/(?:classes|lib)/").unwrap();
+        assert_eq!(
+            strip_entry_prefix("WEB-INF/classes/Foo.class", &pattern),
+            "Foo.class"
+        );
+        // would take ages with backtracking
+        let pattern = Regex::new("(a*)*b").unwrap();
+        let name = "a".repeat(10_000);
+        assert_eq!(strip_entry_prefix(&name, &pattern), name);
+    }
+
+    #[test]
+    fn test_is_class_file_in_jar() {
+        assert!(is_class_file_in_jar("com/example/Foo.class"));
+        assert!(is_class_file_in_jar("Foo.class"));
+        assert!(!is_class_file_in_jar(
+            "META-INF/versions/17/com/example/Foo.class"
+        ));
+        assert!(!is_class_file_in_jar("com/example/messages.properties"));
+    }
+
+    #[test]
+    fn test_is_multi_release_class() {
+        assert!(is_multi_release_class(
+            "META-INF/versions/17/com/example/Foo.class"
+        ));
+        assert!(!is_multi_release_class("META-INF/versions/17/notes.txt"));
+        assert!(!is_multi_release_class(
+            "META-INF/versions/latest/Foo.class"
+        ));
+        assert!(!is_multi_release_class("META-INF/Foo.class"));
+        assert!(!is_multi_release_class("com/example/Foo.class"));
+    }
+
+    #[test]
+    fn test_extracted_jar_multi_release() {
+        let jar = jar_with(&[
+            ("com/example/Foo.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+            (
+                "META-INF/versions/17/com/example/Foo.class",
+                &[202, 254, 186, 190, 0, 0, 0, 61],
+            ),
+        ]);
+        let extracted =
+            ExtractedJar::from_reader(Cursor::new(jar.clone()), &ScanOptions::default()).unwrap();
+        assert_eq!(classes_of(&extracted), vec![java_class(52)]);
+
+        let options = ScanOptions {
+            multi_release: true,
+            ..ScanOptions::default()
+        };
+        let extracted = ExtractedJar::from_reader(Cursor::new(jar), &options).unwrap();
+        assert_eq!(classes_of(&extracted), vec![java_class(52), java_class(61)]);
+    }
+
+    #[test]
+    fn test_include_meta_inf() {
+        let jar = jar_with(&[
+            ("com/example/Foo.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+            (
+                "META-INF/versions/11/module-info.class",
+                &[202, 254, 186, 190, 0, 0, 0, 55],
+            ),
+            (
+                "META-INF/launcher/Boot.class",
+                &[202, 254, 186, 190, 0, 0, 0, 61],
+            ),
+        ]);
+        let extracted =
+            ExtractedJar::from_reader(Cursor::new(jar.clone()), &ScanOptions::default()).unwrap();
+        assert_eq!(classes_of(&extracted), vec![java_class(52)]);
+
+        let options = ScanOptions {
+            include_meta_inf: true,
+            ..ScanOptions::default()
+        };
+        let extracted = ExtractedJar::from_reader(Cursor::new(jar), &options).unwrap();
+        assert_eq!(
+            classes_of(&extracted),
+            vec![java_class(52), java_class(55), java_class(61)]
+        );
+    }
+
+    #[test]
+    fn test_include_exclude() {
+        let jar = jar_with(&[
+            ("com/example/Foo.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+            (
+                "com/example/test/Harness.class",
+                &[202, 254, 186, 190, 0, 0, 0, 65],
+            ),
+            ("org/lib/Bar.class", &[202, 254, 186, 190, 0, 0, 0, 55]),
+        ]);
+        let globs = |patterns: &[&str]| patterns.iter().map(|p| Glob::new(p).unwrap()).collect();
+        let version = |options: &ScanOptions| {
+            let extracted = ExtractedJar::from_reader(Cursor::new(jar.clone()), options).unwrap();
+            archive_result("app.jar", Ok(extracted), options)
+                .unwrap()
+                .version
+        };
+        assert_eq!(version(&ScanOptions::default()), JavaVersion(21));
+
+        let options = ScanOptions {
+            exclude: globs(&["com/example/test/**"]),
+            ..ScanOptions::default()
+        };
+        assert_eq!(version(&options), JavaVersion(11));
+        let options = ScanOptions {
+            include: globs(&["com/example/**"]),
+            ..options
+        };
+        assert_eq!(version(&options), JavaVersion(8));
+        let options = ScanOptions {
+            include: globs(&["Harness.class"]),
+            exclude: Vec::new(),
+            ..ScanOptions::default()
+        };
+        assert_eq!(version(&options), JavaVersion(21));
+    }
+
+    #[test]
+    fn test_extracted_jar_from_zip() {
+        let jar = jar_with(&[
+            ("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\n"),
+            ("com/example/Foo.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+            ("com/example/Bar.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+        ]);
+        let extracted =
+            ExtractedJar::from_reader(Cursor::new(jar), &ScanOptions::default()).unwrap();
+        assert_eq!(classes_of(&extracted), vec![java_class(52), java_class(61)]);
+    }
+
+    #[test]
+    fn test_check_manifest_version() {
+        let jar = jar_with(&[
+            (
+                MANIFEST_PATH,
+                b"Manifest-Version: 1.0\r\nImplementation-Version: 2.1.0\r\n",
+            ),
+            ("Foo.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+        ]);
+        let options = ScanOptions {
+            manifest_version: Some(Regex::new(r"^2\.\d+\.\d+$").unwrap()),
+            ..Default::default()
+        };
+        let extracted = ExtractedJar::from_reader(Cursor::new(jar.clone()), &options).unwrap();
+        assert!(
+            extracted
+                .check_manifest_version(&Regex::new(r"^2\.").unwrap())
+                .is_ok()
+        );
+
+        let result = extracted.check_manifest_version(&Regex::new(r"^3\.").unwrap());
+        assert!(matches!(
+            result,
+            Err(ExtractedJarError::ManifestVersionMismatch { actual, .. }) if actual == "2.1.0"
+        ));
+
+        // no one asked for the manifest, so it isn't read
+        let extracted =
+            ExtractedJar::from_reader(Cursor::new(jar), &ScanOptions::default()).unwrap();
+        assert!(extracted.manifest.is_none());
+    }
+
+    #[test]
+    fn test_check_manifest_version_without_manifest() {
+        let jar = jar_with(&[("Foo.class", &[202, 254, 186, 190, 0, 0, 0, 52])]);
+        let options = ScanOptions {
+            manifest_version: Some(Regex::new(".*").unwrap()),
+            ..Default::default()
+        };
+        let extracted = ExtractedJar::from_reader(Cursor::new(jar), &options).unwrap();
+        assert!(matches!(
+            extracted.check_manifest_version(&Regex::new(".*").unwrap()),
+            Err(ExtractedJarError::MissingManifestVersion(_))
+        ));
+    }
+
+    #[test]
+    fn test_extracted_jar_stops_above_max() {
+        let jar = jar_with(&[
+            ("A.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+            ("B.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+            ("C.class", &[202, 254, 186, 190, 0, 0, 0, 65]),
+        ]);
+        let options = ScanOptions {
+            stop_above: Some(11),
+            ..Default::default()
+        };
+        let extracted = ExtractedJar::from_reader(Cursor::new(jar.clone()), &options).unwrap();
+        assert!(extracted.stopped_early);
+        assert_eq!(classes_of(&extracted), vec![java_class(52), java_class(61)]);
+
+        let options = ScanOptions {
+            stop_above: Some(21),
+            ..Default::default()
+        };
+        let extracted = ExtractedJar::from_reader(Cursor::new(jar), &options).unwrap();
+        assert!(!extracted.stopped_early);
+        assert_eq!(extracted.classfiles.len(), 3);
+    }
+
+    #[test]
+    fn test_extracted_jar_does_not_stop_before_reading_manifest() {
+        let jar = jar_with(&[
+            ("A.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+            (MANIFEST_PATH, b"Implementation-Version: 1.0.0\r\n"),
+            ("B.class", &[202, 254, 186, 190, 0, 0, 0, 65]),
+        ]);
+        let options = ScanOptions {
+            manifest_version: Some(Regex::new("^1").unwrap()),
+            stop_above: Some(11),
+            ..Default::default()
+        };
+        let extracted = ExtractedJar::from_reader(Cursor::new(jar), &options).unwrap();
+        assert!(extracted.stopped_early);
+        assert!(extracted.manifest.is_some());
+        assert_eq!(classes_of(&extracted), vec![java_class(61)]);
+
+        // neither a lower class nor a nested jar that stayed below the maximum make up for the one that didn't
+        let inner = jar_with(&[("Inner.class", &[202, 254, 186, 190, 0, 0, 0, 52])]);
+        let jar = jar_with(&[
+            ("A.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+            ("C.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+            ("lib/inner.jar", &inner),
+            (MANIFEST_PATH, b"Implementation-Version: 1.0.0\r\n"),
+            ("B.class", &[202, 254, 186, 190, 0, 0, 0, 65]),
+        ]);
+        let options = ScanOptions {
+            nested_jars: true,
+            ..options
+        };
+        let extracted = ExtractedJar::from_reader(Cursor::new(jar), &options).unwrap();
+        assert!(extracted.stopped_early);
+        assert!(extracted.manifest.is_some());
+        assert_eq!(
+            classes_of(&extracted),
+            vec![java_class(61), java_class(52), java_class(52)]
+        );
+    }
+
+    #[test]
+    fn test_extracted_jar_counts_debug_info() {
+        let debug = crate::classfile::tests::class_with_code_attribute(Some("LineNumberTable"));
+        let stripped = crate::classfile::tests::class_with_code_attribute(None);
+        let jar = jar_with(&[("Debug.class", &debug), ("Stripped.class", &stripped)]);
+        let options = ScanOptions {
+            check_stripped: true,
+            ..Default::default()
+        };
+        let extracted = ExtractedJar::from_reader(Cursor::new(jar), &options).unwrap();
+        assert_eq!(extracted.with_debug_info, 1);
+        assert_eq!(extracted.classfiles.len(), 2);
+    }
+
+    #[test]
+    fn test_extracted_jar_max_with_name() {
+        let jar = jar_with(&[
+            ("a/Old.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+            ("b/First.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+            ("c/Second.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+        ]);
+        let extracted =
+            ExtractedJar::from_reader(Cursor::new(jar), &ScanOptions::default()).unwrap();
+        assert_eq!(
+            extracted.max_with_name(),
+            Some(("b/First.class", JavaVersion(17)))
+        );
+    }
+
+    #[test]
+    fn test_extracted_jar_check_uniform() {
+        let uniform = jar_with(&[
+            ("a/A.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+            ("b/B.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+        ]);
+        let extracted =
+            ExtractedJar::from_reader(Cursor::new(uniform), &ScanOptions::default()).unwrap();
+        assert!(extracted.check_uniform("uniform.jar").is_ok());
+
+        let mixed = jar_with(&[
+            ("a/A.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+            ("b/B.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+            ("b/C.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+        ]);
+        let extracted =
+            ExtractedJar::from_reader(Cursor::new(mixed), &ScanOptions::default()).unwrap();
+        let error = extracted.check_uniform("mixed.jar").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "mixed.jar contains classes with different versions: Java 8 (e.g. b/B.class), Java 17 (e.g. a/A.class)"
+        );
+    }
+
+    #[test]
+    fn test_extracted_jar_does_not_scan_nested_jars_in_zips() {
+        let inner = jar_with(&[("com/example/Foo.class", &[202, 254, 186, 190, 0, 0, 0, 52])]);
+        let jar = jar_with(&[("lib/inner.jar", &inner)]);
+        let result = ExtractedJar::from_reader(Cursor::new(jar), &ScanOptions::default());
+        assert!(matches!(result, Err(ExtractedJarError::NoClassFiles)));
+    }
+
+    #[test]
+    fn test_extracted_jar_nested_jars() {
+        let class = [202, 254, 186, 190, 0, 0, 0, 52];
+        let mut jar = jar_with(&[("com/example/Foo.class", &class)]);
+        for depth in 1..=3 {
+            jar = jar_with(&[(&format!("lib/level{depth}.jar"), &jar)]);
+        }
+        let options = ScanOptions {
+            nested_jars: true,
+            ..ScanOptions::default()
+        };
+        let extracted = ExtractedJar::from_reader(Cursor::new(jar.clone()), &options).unwrap();
+        assert_eq!(
+            extracted.classfiles[0].0,
+            "lib/level3.jar!/lib/level2.jar!/lib/level1.jar!/com/example/Foo.class"
+        );
+
+        let options = ScanOptions {
+            max_nesting_depth: Some(2),
+            ..options
+        };
+        let error = ExtractedJar::from_reader(Cursor::new(jar), &options)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "lib/level3.jar!/lib/level2.jar!/lib/level1.jar is nested more than 2 jar(s) deep, not going any further"
+        );
+    }
+
+    #[test]
+    fn test_archive_result_histogram() {
+        let jar = jar_with(&[
+            ("A.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+            ("B.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+            ("C.class", &[202, 254, 186, 190, 0, 0, 0, 52]),
+        ]);
+        let options = ScanOptions::default();
+        let extracted = ExtractedJar::from_reader(Cursor::new(jar), &options);
+        let result = archive_result("app.jar", extracted, &options).unwrap();
+        assert_eq!(result.version, JavaVersion(17));
+        assert_eq!(result.histogram, BTreeMap::from([(52, 2), (61, 1)]));
+    }
+
+    #[test]
+    fn test_process_archive_too_large() {
+        let class = [202, 254, 186, 190, 0, 0, 0, 52];
+        let inner = jar_with(&[("Foo.class", &class), ("Bar.class", &class)]);
+        let dir = TempDir::new("too-large");
+        let file = dir.join("app.jar");
+        fs::write(&file, jar_with(&[("lib/inner.jar", &inner)])).unwrap();
+        let file = file.to_str().unwrap();
+
+        let options = ScanOptions {
+            nested_jars: true,
+            max_entries: Some(1),
+            ..ScanOptions::default()
+        };
+        assert!(matches!(
+            process_archive(file, &options),
+            Err(ExtractedJarError::TooLarge { entry, .. }) if entry == format!("{file}!/lib/inner.jar")
+        ));
+        let options = ScanOptions {
+            max_entries: None,
+            max_entry_bytes: Some(inner.len() as u64 - 1),
+            ..options
+        };
+        assert!(matches!(
+            process_archive(file, &options),
+            Err(ExtractedJarError::TooLarge { entry, .. }) if entry == format!("{file}!/lib/inner.jar")
+        ));
+        let options = ScanOptions {
+            max_entry_bytes: Some(inner.len() as u64),
+            ..options
+        };
+        assert_eq!(
+            process_archive(file, &options).unwrap().version,
+            JavaVersion(8)
+        );
+    }
+
+    /// Not a real benchmark, but shows whether reading large jars got slower: `cargo test --release -- --ignored`
+    #[test]
+    #[ignore = "only meant to be timed"]
+    fn test_process_archive_many_entries() {
+        // only the first 8 bytes of each are needed, the rest is what shouldn't have to be inflated
+        let mut class = vec![202, 254, 186, 190, 0, 0, 0, 61];
+        class.extend((0..16 * 1024).map(|i| (i % 251) as u8));
+        let names: Vec<_> = (0..5000)
+            .map(|i| format!("p{}/C{i}.class", i % 50))
+            .collect();
+        let entries: Vec<_> = names
+            .iter()
+            .map(|name| (name.as_str(), class.as_slice()))
+            .collect();
+        let dir = TempDir::new("many-entries");
+        let file = dir.join("big.jar");
+        fs::write(&file, jar_with(&entries)).unwrap();
+
+        let start = std::time::Instant::now();
+        let result = process_archive(file.to_str().unwrap(), &ScanOptions::default()).unwrap();
+        eprintln!("{} entries took {:?}", names.len(), start.elapsed());
+        assert_eq!(result.version, JavaVersion(17));
+        assert_eq!(result.histogram, BTreeMap::from([(61, names.len())]));
+    }
+
+    #[test]
+    fn test_extracted_jar_scans_libraries_of_bundles() {
+        let dependency = jar_with(&[("org/dep/Dep.class", &[202, 254, 186, 190, 0, 0, 0, 61])]);
+        let war = jar_with(&[
+            (
+                "WEB-INF/classes/com/example/Servlet.class",
+                &[202, 254, 186, 190, 0, 0, 0, 52],
+            ),
+            ("WEB-INF/lib/dep.jar", &dependency),
+        ]);
+        let ear = jar_with(&[("app.war", &war)]);
+        let mut archive = zip::ZipArchive::new(Cursor::new(ear)).unwrap();
+        let extracted =
+            ExtractedJar::from_archive(&mut Bundle(&mut archive), &ScanOptions::default()).unwrap();
+        let names: Vec<_> = extracted
+            .classfiles
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "app.war!/WEB-INF/classes/com/example/Servlet.class",
+                "app.war!/WEB-INF/lib/dep.jar!/org/dep/Dep.class"
+            ]
+        );
+        assert_eq!(extracted.class_count(), 2);
+        assert!(is_bundle("target/app.war") && is_bundle("app.ear") && !is_bundle("app.jar"));
+    }
+
+    #[test]
+    fn test_scan_file_zip_and_aar() {
+        let dir = TempDir::new("zip-aar");
+        let zip = dir.join("classes.zip");
+        fs::write(
+            &zip,
+            jar_with(&[("Foo.class", &[202, 254, 186, 190, 0, 0, 0, 61])]),
+        )
+        .unwrap();
+        let classes = jar_with(&[("com/example/Lib.class", &[202, 254, 186, 190, 0, 0, 0, 52])]);
+        let aar = dir.join("lib.aar");
+        fs::write(
+            &aar,
+            jar_with(&[
+                ("AndroidManifest.xml", b"<manifest/>"),
+                ("classes.jar", &classes),
+            ]),
+        )
+        .unwrap();
+        let options = ScanOptions {
+            keep_classes: true,
+            ..Default::default()
+        };
+
+        let (zip, aar) = (zip.to_str().unwrap(), aar.to_str().unwrap());
+        assert_eq!(Format::by_name(zip), Some(Format::Zip));
+        assert_eq!(Format::by_name(aar), Some(Format::Zip));
+        let result = scan_file(zip, &options).unwrap();
+        assert_eq!(result.version, JavaVersion(17));
+        assert_eq!(result.classes, [("Foo.class".to_owned(), JavaVersion(17))]);
+        // no --nested-jars needed for the classes of an aar
+        let result = scan_file(aar, &options).unwrap();
+        assert_eq!(result.version, JavaVersion(8));
+        assert_eq!(
+            result.classes,
+            [(
+                "classes.jar!/com/example/Lib.class".to_owned(),
+                JavaVersion(8)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_extracted_jar_fails_on_preview() {
+        const PREVIEW: [u8; 8] = [202, 254, 186, 190, 255, 255, 0, 65];
+        let options = ScanOptions {
+            fail_on_preview: true,
+            ..ScanOptions::default()
+        };
+        let inner = jar_with(&[("com/example/Preview.class", &PREVIEW)]);
+        let nested = jar_with(&[
+            ("app/Main.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+            ("lib/inner.jar", &inner),
+        ]);
+        let error = ExtractedJar::from_reader(Cursor::new(nested.clone()), &options)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "lib/inner.jar!/com/example/Preview.class was compiled with --enable-preview"
+        );
+        // nothing to complain about without the flag, and the nested jar still doesn't count
+        let extracted =
+            ExtractedJar::from_reader(Cursor::new(nested), &ScanOptions::default()).unwrap();
+        assert_eq!(classes_of(&extracted), vec![java_class(61)]);
+
+        let multi_release = jar_with(&[
+            ("app/Main.class", &[202, 254, 186, 190, 0, 0, 0, 61]),
+            ("META-INF/versions/21/app/Main.class", &PREVIEW),
+        ]);
+        assert!(matches!(
+            ExtractedJar::from_reader(Cursor::new(multi_release), &options),
+            Err(ExtractedJarError::Preview(class)) if class == "META-INF/versions/21/app/Main.class"
+        ));
+
+        // before Java 12, that minor version doesn't mean anything
+        let old = jar_with(&[("app/Main.class", &[202, 254, 186, 190, 255, 255, 0, 52])]);
+        assert!(ExtractedJar::from_reader(Cursor::new(old), &options).is_ok());
+    }
+
+    /// An archive that takes its time handing out a single class
+    struct SlowArchive(Duration);
+
+    impl ArchiveReader for SlowArchive {
+        fn for_each_entry(&mut self, visit: &mut EntryVisitor) -> Result<(), ExtractedJarError> {
+            thread::sleep(self.0);
+            // only one entry, no point in looking at whether to stop
+            let _ = visit(
+                "Foo.class",
+                &mut [202, 254, 186, 190, 0, 0, 0, 61].as_slice(),
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_with_timeout() {
+        let scan = |delay| {
+            move || {
+                ExtractedJar::from_archive(&mut SlowArchive(delay), &ScanOptions::default())
+                    .map(|jar| classes_of(&jar))
+            }
+        };
+        let result = with_timeout(Duration::from_millis(50), scan(Duration::from_secs(5)));
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "scanning took longer than 50ms"
+        );
+
+        let result = with_timeout(Duration::from_secs(5), scan(Duration::ZERO));
+        assert_eq!(result.unwrap().unwrap(), vec![java_class(61)]);
+    }
+
+    #[test]
+    fn test_java_version_ordering() {
+        let v8 = JavaVersion(8);
+        let v11 = JavaVersion(11);
+        let v17 = JavaVersion(17);
+
+        assert!(v8 < v11);
+        assert!(v11 < v17);
+        assert!(v8 < v17);
+    }
+
+    #[test]
+    fn test_java_version_hash() {
+        let versions: HashSet<_> = [JavaVersion(17), JavaVersion(8), JavaVersion(17)]
+            .into_iter()
+            .collect();
+        assert_eq!(versions.len(), 2);
+        assert!(versions.contains(&JavaVersion(8)));
+        assert!(!versions.contains(&JavaVersion(11)));
+
+        let classes: HashSet<_> = [java_class(61), java_class(61)].into_iter().collect();
+        assert_eq!(classes.len(), 1);
+    }
+
+    #[test]
+    fn test_java_class_ordering() {
+        let c50 = java_class(50);
+        let c52 = java_class(52);
+        let c55 = java_class(55);
+
+        assert!(c50 < c52);
+        assert!(c52 < c55);
+        assert!(c50 < c55);
+    }
+}
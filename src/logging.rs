@@ -0,0 +1,118 @@
+//! Where the `tracing` events of the binary end up. Using this as a library doesn't print anything on its own, that
+//! takes [`init`] (or a subscriber of your own).
+
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use tracing::{Level, Metadata, level_filters::LevelFilter};
+use tracing_subscriber::{EnvFilter, fmt::MakeWriter};
+
+/// What was logged on this thread while [`capture`] is running
+pub(crate) type Logged = Vec<(Level, String)>;
+
+/// Set for structured output, so that stdout only contains the output and nothing else
+static INFO_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Logged>> = const { RefCell::new(None) };
+}
+
+/// Logs to the terminal from now on: up to `INFO` (which is what the binary prints by default), `DEBUG` with one `-v`
+/// and `TRACE` with more, unless `RUST_LOG` says otherwise. `INFO` goes to stdout unless `info_to_stderr` is set,
+/// everything else to stderr. Only works once, returns whether it did
+pub fn init(verbosity: u8, info_to_stderr: bool) -> bool {
+    INFO_TO_STDERR.store(info_to_stderr, Ordering::Relaxed);
+    let level = match verbosity {
+        0 => LevelFilter::INFO,
+        1 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::builder()
+                .with_default_directive(level.into())
+                .from_env_lossy(),
+        )
+        .with_writer(Terminal)
+        .without_time()
+        .with_level(false)
+        .with_target(false)
+        .with_ansi(false)
+        .try_init()
+        .is_ok()
+}
+
+/// Holds back everything `f` logs on this thread instead of logging it, for [`replay`] to log later. Work that runs on
+/// threads of its own would mix up what it logs otherwise
+pub(crate) fn capture<T>(f: impl FnOnce() -> T) -> (T, Logged) {
+    let outer = CAPTURED.replace(Some(Vec::new()));
+    let result = f();
+    let logged = CAPTURED.replace(outer).unwrap_or_default();
+    (result, logged)
+}
+
+/// Logs what [`capture`] held back, as if it was logged right now
+pub(crate) fn replay(logged: Logged) {
+    for (level, line) in logged {
+        emit(level, line);
+    }
+}
+
+fn emit(level: Level, line: String) {
+    let line = CAPTURED.with_borrow_mut(|captured| match captured {
+        Some(logged) => {
+            logged.push((level, line));
+            None
+        }
+        None => Some(line),
+    });
+    match line {
+        Some(line) if level == Level::INFO && !INFO_TO_STDERR.load(Ordering::Relaxed) => {
+            println!("{line}")
+        }
+        Some(line) => eprintln!("{line}"),
+        None => {}
+    }
+}
+
+/// Hands out a [`Line`] for every event
+struct Terminal;
+
+impl<'a> MakeWriter<'a> for Terminal {
+    type Writer = Line;
+
+    fn make_writer(&'a self) -> Line {
+        Line(Level::INFO, Vec::new())
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Line {
+        Line(*meta.level(), Vec::new())
+    }
+}
+
+/// One formatted event, which is only logged (or captured) as a whole once it's complete
+struct Line(Level, Vec<u8>);
+
+impl Write for Line {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.1.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Line {
+    fn drop(&mut self) {
+        if self.1.is_empty() {
+            return;
+        }
+        let line = String::from_utf8_lossy(&self.1);
+        emit(self.0, line.trim_end_matches('\n').to_owned());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::JavaVersion;
+
+/// Where a jar keeps its manifest
+pub const MANIFEST_PATH: &str = "META-INF/MANIFEST.MF";
+
+/// The main section of a `META-INF/MANIFEST.MF`.
+///
+/// Per-entry sections (the ones starting with `Name:`) are ignored, nothing here needs them.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    attributes: Vec<(String, String)>,
+}
+
+impl Manifest {
+    /// Parses the `Key: Value` lines of the main section. Lines starting with a single space continue the previous value,
+    /// as the spec wraps lines at 72 bytes.
+    pub fn parse(content: &str) -> Self {
+        let mut attributes: Vec<(String, String)> = Vec::new();
+        for line in content.lines() {
+            let line = line.trim_end_matches('\r');
+            // an empty line ends the main section
+            if line.is_empty() {
+                break;
+            }
+            if let Some(continuation) = line.strip_prefix(' ') {
+                if let Some((_, value)) = attributes.last_mut() {
+                    value.push_str(continuation);
+                }
+            } else if let Some((key, value)) = line.split_once(':') {
+                attributes.push((key.trim().to_owned(), value.trim().to_owned()));
+            }
+        }
+        Self { attributes }
+    }
+
+    /// Value of the given attribute. Attribute names are case-insensitive
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The JDK the jar was built with according to `Build-Jdk-Spec` (Maven, Gradle) or `Build-Jdk` (older Maven),
+    /// along with the attribute it came from
+    pub fn build_jdk(&self) -> Option<(&'static str, JavaVersion)> {
+        ["Build-Jdk-Spec", "Build-Jdk"]
+            .into_iter()
+            .find_map(|key| Some((key, jdk_version(self.get(key)?)?)))
+    }
+}
+
+/// The feature release of a JDK version like `17`, `17.0.2+8` or `1.8.0_292`
+fn jdk_version(version: &str) -> Option<JavaVersion> {
+    let mut numbers = version
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|number| !number.is_empty());
+    let first = numbers.next()?.parse().ok()?;
+    let feature = match first {
+        1 => numbers.next()?.parse().ok()?,
+        first => first,
+    };
+    Some(JavaVersion(feature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_parse() {
+        let manifest = Manifest::parse(
+            "Manifest-Version: 1.0\r\nImplementation-Version: 1.2.3-SNAPSH\r\n OT\r\nbuild-jdk-spec: 17\r\n\r\nName: com/example/\r\nImplementation-Version: 0.0.1\r\n",
+        );
+        assert_eq!(manifest.get("Manifest-Version"), Some("1.0"));
+        assert_eq!(
+            manifest.get("Implementation-Version"),
+            Some("1.2.3-SNAPSHOT")
+        );
+        assert_eq!(manifest.get("Build-Jdk-Spec"), Some("17"));
+        assert_eq!(manifest.get("Name"), None);
+    }
+
+    #[test]
+    fn test_manifest_build_jdk() {
+        let build_jdk = |content: &str| Manifest::parse(content).build_jdk();
+        assert_eq!(
+            build_jdk("Build-Jdk-Spec: 17\nBuild-Jdk: 21.0.1\n"),
+            Some(("Build-Jdk-Spec", JavaVersion(17)))
+        );
+        assert_eq!(
+            build_jdk("Build-Jdk: 1.8.0_292\n"),
+            Some(("Build-Jdk", JavaVersion(8)))
+        );
+        assert_eq!(
+            build_jdk("Build-Jdk: 11.0.12 (Eclipse Adoptium)\n"),
+            Some(("Build-Jdk", JavaVersion(11)))
+        );
+        assert_eq!(build_jdk("Build-Jdk: unknown\n"), None);
+        assert_eq!(build_jdk("Manifest-Version: 1.0\n"), None);
+    }
+}
@@ -0,0 +1,162 @@
+//! Combines JSON reports from earlier runs (e.g. CI shards) into one.
+
+use std::{collections::HashMap, fs};
+
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::report::{FileReport, SCHEMA_VERSION};
+
+#[derive(Error, Debug)]
+pub enum MergeError {
+    #[error("Failed to read report {path}")]
+    IO {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("Failed to parse report {path}")]
+    Json {
+        path: String,
+        source: serde_json::Error,
+    },
+    #[error("{path} is not a report: {reason}")]
+    NotAReport { path: String, reason: &'static str },
+    #[error("{path} has schemaVersion {actual}, only {SCHEMA_VERSION} is supported")]
+    UnsupportedSchema { path: String, actual: f64 },
+}
+
+/// Reads the `files` out of a report written by `--format json`
+pub fn read_report(path: &str) -> Result<Vec<FileReport>, MergeError> {
+    let content = fs::read_to_string(path).map_err(|source| MergeError::IO {
+        path: path.to_owned(),
+        source,
+    })?;
+    let invalid = |source| MergeError::Json {
+        path: path.to_owned(),
+        source,
+    };
+    let json: Value = serde_json::from_str(&content).map_err(invalid)?;
+    let not_a_report = |reason| MergeError::NotAReport {
+        path: path.to_owned(),
+        reason,
+    };
+
+    let schema = json
+        .get("schemaVersion")
+        .and_then(Value::as_f64)
+        .ok_or_else(|| not_a_report("no schemaVersion"))?;
+    if schema != f64::from(SCHEMA_VERSION) {
+        return Err(MergeError::UnsupportedSchema {
+            path: path.to_owned(),
+            actual: schema,
+        });
+    }
+    let Some(Value::Array(files)) = json.get("files") else {
+        return Err(not_a_report("no files"));
+    };
+    files
+        .iter()
+        // files that couldn't be scanned back then don't have anything to merge
+        .filter(|file| file.get("error").is_none())
+        .map(|file| FileReport::deserialize(file).map_err(invalid))
+        .collect()
+}
+
+/// Puts all reports together. A file that shows up more than once keeps the highest version, otherwise the order
+/// stays the same as in the input
+pub fn merge(reports: impl IntoIterator<Item = FileReport>) -> Vec<FileReport> {
+    let mut merged: Vec<FileReport> = Vec::new();
+    let mut seen = HashMap::new();
+    for report in reports {
+        match seen.get(&report.path) {
+            Some(&index) => {
+                let existing: &mut FileReport = &mut merged[index];
+                if report.version > existing.version {
+                    *existing = report;
+                }
+            }
+            None => {
+                seen.insert(report.path.clone(), merged.len());
+                merged.push(report);
+            }
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        report::{pretty, render_json},
+        tests::{TempDir, report_of},
+    };
+
+    fn write_report(dir: &TempDir, name: &str, report: &[FileReport]) -> String {
+        let path = dir.join(format!("{name}.json"));
+        fs::write(&path, pretty(&render_json(report))).unwrap();
+        path.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn test_merge_two_reports() {
+        let dir = TempDir::new("merge");
+        let first = write_report(&dir, "shard-1", &report_of(&[("a.jar", 8), ("b.jar", 11)]));
+        let second = write_report(&dir, "shard-2", &report_of(&[("b.jar", 17), ("c.jar", 8)]));
+
+        let merged = merge(
+            [&first, &second]
+                .into_iter()
+                .flat_map(|path| read_report(path).unwrap()),
+        );
+        assert_eq!(
+            merged,
+            report_of(&[("a.jar", 8), ("b.jar", 17), ("c.jar", 8)])
+        );
+    }
+
+    #[test]
+    fn test_merge_keeps_higher_version() {
+        let merged = merge(report_of(&[("a.jar", 17), ("a.jar", 11)]));
+        assert_eq!(merged, report_of(&[("a.jar", 17)]));
+    }
+
+    #[test]
+    fn test_read_report_skips_errors() {
+        let dir = TempDir::new("errors");
+        let path = dir.join("report.json");
+        fs::write(
+            &path,
+            r#"{"schemaVersion": 1, "files": [{"path": "a.jar", "version": 8}, {"path": "b.jar", "error": "Not a jar or tar file"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            read_report(path.to_str().unwrap()).unwrap(),
+            report_of(&[("a.jar", 8)])
+        );
+    }
+
+    #[test]
+    fn test_read_report_invalid() {
+        let dir = TempDir::new("not-a-report");
+        let path = dir.join("report.json");
+        fs::write(
+            &path,
+            r#"{"schemaVersion": 1, "files": [{"path": "a.jar"}]}"#,
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+        assert!(matches!(read_report(path), Err(MergeError::Json { .. })));
+        fs::write(path, r#"{"files": []}"#).unwrap();
+        assert!(matches!(
+            read_report(path),
+            Err(MergeError::NotAReport { .. })
+        ));
+        fs::write(path, r#"{"schemaVersion": 2, "files": []}"#).unwrap();
+        assert!(matches!(
+            read_report(path),
+            Err(MergeError::UnsupportedSchema { .. })
+        ));
+    }
+}
@@ -0,0 +1,175 @@
+//! `--jobs`, scanning several inputs at the same time while everything that comes out of it still shows up in the
+//! order of the inputs, as if they were scanned one after the other.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc, Mutex, PoisonError,
+        mpsc::{self, Receiver, Sender},
+    },
+    thread::Scope,
+};
+
+use crate::logging::{self, Logged};
+
+/// Calls `f` for every item on up to `jobs` threads of `scope` and hands out the results in the order of the items.
+/// What `f` logs is held back until its result is handed out. Dropping the iterator stops the threads after the items
+/// they are busy with. With a single job, nothing is done before it is asked for, otherwise no more than two items per
+/// job are done ahead of the one that is up next
+pub fn map_in_order<'scope, 'env, T: Sync, R: Send + 'scope>(
+    scope: &'scope Scope<'scope, 'env>,
+    items: &'env [T],
+    jobs: usize,
+    f: &'env (impl Fn(&T) -> R + Sync),
+) -> Box<dyn Iterator<Item = R> + 'scope> {
+    if jobs <= 1 || items.len() <= 1 {
+        return Box::new(items.iter().map(f));
+    }
+    // the indices of the items that may be started, for whichever thread is free first
+    let (todo, started) = mpsc::channel();
+    let started = Arc::new(Mutex::new(started));
+    let (sender, receiver) = mpsc::channel();
+    for _ in 0..jobs.min(items.len()) {
+        let (started, sender) = (started.clone(), sender.clone());
+        scope.spawn(move || {
+            loop {
+                // the lock is only released again once there is an index, or there won't be any more
+                let next = started
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .recv();
+                let Ok(index) = next else {
+                    break;
+                };
+                // nobody listening anymore means that the rest isn't needed either
+                if sender
+                    .send((index, logging::capture(|| f(&items[index]))))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+    }
+    let mut in_order = InOrder {
+        receiver,
+        pending: BTreeMap::new(),
+        next: 0,
+        todo: Some(todo),
+        window: jobs * 2,
+        len: items.len(),
+    };
+    for index in 0..in_order.window.min(items.len()) {
+        in_order.allow(index);
+    }
+    Box::new(in_order)
+}
+
+/// Puts the results back in order, as they come in as soon as they are done
+struct InOrder<R> {
+    receiver: Receiver<(usize, (R, Logged))>,
+    /// done already, but waiting for the ones before them
+    pending: BTreeMap<usize, (R, Logged)>,
+    next: usize,
+    /// where further items are allowed to start, until all of them are
+    todo: Option<Sender<usize>>,
+    /// how many items can be taken on before the one that is up next is handed out
+    window: usize,
+    len: usize,
+}
+
+impl<R> InOrder<R> {
+    fn allow(&mut self, index: usize) {
+        if let Some(todo) = &self.todo {
+            // the threads only go away once there is no sender anymore
+            let _ = todo.send(index);
+        }
+        if index + 1 >= self.len {
+            self.todo = None;
+        }
+    }
+}
+
+impl<R> Iterator for InOrder<R> {
+    type Item = R;
+
+    fn next(&mut self) -> Option<R> {
+        loop {
+            if let Some((result, logged)) = self.pending.remove(&self.next) {
+                self.next += 1;
+                if self.next + self.window <= self.len {
+                    self.allow(self.next + self.window - 1);
+                }
+                logging::replay(logged);
+                return Some(result);
+            }
+            // every thread is done once there is nothing left to receive
+            let (index, done) = self.receiver.recv().ok()?;
+            self.pending.insert(index, done);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+        time::Duration,
+    };
+
+    #[test]
+    fn test_map_in_order() {
+        // nothing is logged (or captured) without a subscriber
+        logging::init(0, false);
+        let items: Vec<u64> = (0..20).collect();
+        // the first items take the longest, so they are done last
+        let slow = |item: &u64| {
+            thread::sleep(Duration::from_millis(20 - item));
+            tracing::info!("item {item}");
+            item * 2
+        };
+        for jobs in [1, 4] {
+            let (results, logged) = logging::capture(|| {
+                thread::scope(|scope| map_in_order(scope, &items, jobs, &slow).collect::<Vec<_>>())
+            });
+            assert_eq!(
+                results,
+                items.iter().map(|item| item * 2).collect::<Vec<_>>()
+            );
+            let lines: Vec<_> = logged.into_iter().map(|(_, line)| line).collect();
+            assert_eq!(
+                lines,
+                items
+                    .iter()
+                    .map(|item| format!("item {item}"))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_map_in_order_doesnt_run_ahead() {
+        let items: Vec<u64> = (0..40).collect();
+        let started = AtomicUsize::new(0);
+        let started_before_first = AtomicUsize::new(0);
+        // everything else is done long before the first one is
+        let slow_first = |item: &u64| {
+            started.fetch_add(1, Ordering::Relaxed);
+            if *item == 0 {
+                thread::sleep(Duration::from_millis(100));
+                started_before_first.store(started.load(Ordering::Relaxed), Ordering::Relaxed);
+            }
+            *item
+        };
+        let results: Vec<_> =
+            thread::scope(|scope| map_in_order(scope, &items, 4, &slow_first).collect());
+        assert_eq!(results, items);
+        let started_before_first = started_before_first.into_inner();
+        assert!(
+            started_before_first <= 8,
+            "{started_before_first} items were started"
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! `--policy`: named maximums for groups of inputs, e.g. one for the application and a more relaxed one for dependencies.
+
+use thiserror::Error;
+
+use crate::{JavaVersion, glob::Glob};
+
+#[derive(Debug, Clone)]
+pub struct Policy {
+    pub name: String,
+    pub max: u16,
+    /// inputs the policy applies to, all of them if not set
+    pub glob: Option<Glob>,
+}
+
+impl Policy {
+    /// Parses `NAME:MAX` or `NAME:MAX:GLOB`
+    pub fn parse(policy: &str) -> Result<Self, String> {
+        let mut parts = policy.splitn(3, ':');
+        let (Some(name), Some(max)) = (parts.next(), parts.next()) else {
+            return Err(format!("expected NAME:MAX[:GLOB], got `{policy}`"));
+        };
+        if name.is_empty() {
+            return Err(format!("policy `{policy}` needs a name"));
+        }
+        let max = max.parse::<JavaVersion>().map_err(|e| e.to_string())?;
+        let glob = parts
+            .next()
+            .map(Glob::new)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            name: name.to_owned(),
+            max: *max,
+            glob,
+        })
+    }
+
+    pub fn applies_to(&self, path: &str) -> bool {
+        self.glob.as_ref().is_none_or(|glob| glob.is_match(path))
+    }
+}
+
+/// How the inputs did against one policy
+#[derive(Debug)]
+pub struct Verdict {
+    pub policy: Policy,
+    /// number of inputs the policy applied to
+    pub files: usize,
+    pub violations: Vec<(String, JavaVersion)>,
+}
+
+#[derive(Error, Debug, PartialEq)]
+#[error("Policies failed: {}", .0.join(", "))]
+pub struct PolicyError(Vec<String>);
+
+/// Collects the verdict of every policy while the files come in
+#[derive(Debug)]
+pub struct Policies(Vec<Verdict>);
+
+impl Policies {
+    pub fn new(policies: Vec<Policy>) -> Self {
+        Self(
+            policies
+                .into_iter()
+                .map(|policy| Verdict {
+                    policy,
+                    files: 0,
+                    violations: Vec::new(),
+                })
+                .collect(),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn check(&mut self, path: &str, version: &JavaVersion) {
+        for verdict in &mut self.0 {
+            if !verdict.policy.applies_to(path) {
+                continue;
+            }
+            verdict.files += 1;
+            if **version > verdict.policy.max {
+                verdict.violations.push((path.to_owned(), version.clone()));
+            }
+        }
+    }
+
+    /// One line per policy, plus one for every file that violates it
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for verdict in &self.0 {
+            let Verdict {
+                policy,
+                files,
+                violations,
+            } = verdict;
+            let outcome = if violations.is_empty() {
+                "passed"
+            } else {
+                "FAILED"
+            };
+            out.push_str(&format!(
+                "Policy {} ({} or lower): {outcome}, {} of {files} file(s) too high\n",
+                policy.name,
+                JavaVersion(policy.max),
+                violations.len()
+            ));
+            for (path, version) in violations {
+                out.push_str(&format!("  {path}: {version}\n"));
+            }
+        }
+        out
+    }
+
+    pub fn result(&self) -> Result<(), PolicyError> {
+        let failed: Vec<_> = self
+            .0
+            .iter()
+            .filter(|verdict| !verdict.violations.is_empty())
+            .map(|verdict| verdict.policy.name.clone())
+            .collect();
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(PolicyError(failed))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_parse() {
+        let policy = Policy::parse("app:17").unwrap();
+        assert_eq!((policy.name.as_str(), policy.max), ("app", 17));
+        assert!(policy.applies_to("anything.jar"));
+
+        let policy = Policy::parse("deps:1.8:lib/*.jar").unwrap();
+        assert_eq!(policy.max, 8);
+        assert!(policy.applies_to("lib/foo.jar"));
+        assert!(!policy.applies_to("app.jar"));
+
+        assert!(Policy::parse("app").is_err());
+        assert!(Policy::parse(":17").is_err());
+        assert!(Policy::parse("app:oops").is_err());
+    }
+
+    #[test]
+    fn test_policies() {
+        let mut policies = Policies::new(vec![
+            Policy::parse("app:17:app/**").unwrap(),
+            Policy::parse("deps:11:lib/*.jar").unwrap(),
+        ]);
+        policies.check("app/main.jar", &JavaVersion(17));
+        policies.check("lib/a.jar", &JavaVersion(8));
+        policies.check("lib/b.jar", &JavaVersion(17));
+        assert_eq!(
+            policies.render(),
+            "Policy app (Java 17 or lower): passed, 0 of 1 file(s) too high\n\
+             Policy deps (Java 11 or lower): FAILED, 1 of 2 file(s) too high\n  lib/b.jar: Java 17\n"
+        );
+        assert_eq!(
+            policies.result().unwrap_err().to_string(),
+            "Policies failed: deps"
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! `--progress`, a bar on stderr for archives that take a while. Only ever drawn if the binary turned it on, so that
+//! using this as a library doesn't print anything on its own.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Smaller archives are done before a bar would be of any use
+const MIN_ENTRIES: usize = 1000;
+const WIDTH: usize = 30;
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// How far into an archive scanning is, by entries. Shared between the threads that scan the slices of a zip
+pub struct Progress {
+    file: String,
+    total: usize,
+    done: AtomicUsize,
+    /// percent that was drawn last, to only draw when something changes
+    drawn: AtomicUsize,
+}
+
+impl Progress {
+    /// `None` unless progress is enabled and the archive is large enough for it
+    pub fn start(file: &str, total: usize) -> Option<Self> {
+        (ENABLED.load(Ordering::Relaxed) && total >= MIN_ENTRIES).then(|| Self::new(file, total))
+    }
+
+    /// Draws no matter what, see [`Progress::start`]
+    pub fn new(file: &str, total: usize) -> Self {
+        Self {
+            file: file.to_owned(),
+            total,
+            done: AtomicUsize::new(0),
+            drawn: AtomicUsize::new(0),
+        }
+    }
+
+    /// How many entries are done so far
+    #[cfg(test)]
+    pub fn done(&self) -> usize {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    /// One more entry is done
+    pub fn tick(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        let percent = percent(done, self.total);
+        if self.drawn.fetch_max(percent, Ordering::Relaxed) < percent {
+            eprint!("\r{}", self.line(done));
+        }
+    }
+
+    fn line(&self, done: usize) -> String {
+        let filled = WIDTH * done.min(self.total) / self.total;
+        format!(
+            "{} [{}{}] {:>3}% ({done}/{} entries)",
+            self.file,
+            "#".repeat(filled),
+            "-".repeat(WIDTH - filled),
+            percent(done, self.total),
+            self.total
+        )
+    }
+}
+
+fn percent(done: usize, total: usize) -> usize {
+    100 * done.min(total) / total
+}
+
+impl Drop for Progress {
+    /// Whatever comes next gets the line to itself
+    fn drop(&mut self) {
+        if self.drawn.load(Ordering::Relaxed) > 0 {
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_line() {
+        let progress = Progress::new("app.jar", 40_000);
+        assert_eq!(
+            progress.line(10_000),
+            "app.jar [#######-----------------------]  25% (10000/40000 entries)"
+        );
+        assert_eq!(
+            progress.line(40_000),
+            format!("app.jar [{}] 100% (40000/40000 entries)", "#".repeat(WIDTH))
+        );
+        // nothing is drawn unless the binary asks for it
+        assert!(Progress::start("app.jar", 40_000).is_none());
+    }
+}
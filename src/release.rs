@@ -0,0 +1,142 @@
+//! Java releases by name, for anyone who'd rather `match` on `Java17` than on a number.
+
+use std::fmt::{self, Display};
+
+use crate::JavaVersion;
+
+/// A release of Java, as [`JavaVersion::release`] hands it out. Everything this doesn't know about yet (and
+/// `JavaVersion(0)`, which is what no classes at all come out as) is [`JavaRelease::Unknown`] with the version number.
+/// There is no order on purpose, as an unknown release could be anything. Compare [`JavaVersion`]s for that
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JavaRelease {
+    /// major version 45, which 1.0 used as well
+    Java1_1,
+    Java1_2,
+    Java1_3,
+    Java1_4,
+    Java5,
+    Java6,
+    Java7,
+    Java8,
+    Java9,
+    Java10,
+    Java11,
+    Java12,
+    Java13,
+    Java14,
+    Java15,
+    Java16,
+    Java17,
+    Java18,
+    Java19,
+    Java20,
+    Java21,
+    Java22,
+    Java23,
+    Java24,
+    Java25,
+    Unknown(u16),
+}
+
+/// In order, starting with `JavaVersion(1)`
+const KNOWN: [JavaRelease; 25] = {
+    use JavaRelease::*;
+    [
+        Java1_1, Java1_2, Java1_3, Java1_4, Java5, Java6, Java7, Java8, Java9, Java10, Java11,
+        Java12, Java13, Java14, Java15, Java16, Java17, Java18, Java19, Java20, Java21, Java22,
+        Java23, Java24, Java25,
+    ]
+};
+
+impl JavaRelease {
+    /// The number that [`JavaVersion`] uses for this release
+    pub fn version(&self) -> u16 {
+        match self {
+            Self::Unknown(version) => *version,
+            known => KNOWN
+                .iter()
+                .position(|release| release == known)
+                .map_or(0, |i| i as u16 + 1),
+        }
+    }
+}
+
+impl From<&JavaVersion> for JavaRelease {
+    fn from(value: &JavaVersion) -> Self {
+        usize::from(value.0)
+            .checked_sub(1)
+            .and_then(|i| KNOWN.get(i))
+            .copied()
+            .unwrap_or(Self::Unknown(value.0))
+    }
+}
+
+impl From<JavaVersion> for JavaRelease {
+    fn from(value: JavaVersion) -> Self {
+        Self::from(&value)
+    }
+}
+
+impl From<JavaRelease> for JavaVersion {
+    fn from(value: JavaRelease) -> Self {
+        Self(value.version())
+    }
+}
+
+impl Display for JavaRelease {
+    /// The names the releases were given, `1.x` up to 1.4 and just the number from 5 on
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.version() {
+            version @ 1..=4 => write!(f, "Java 1.{version}"),
+            version if matches!(self, Self::Unknown(_)) => write!(f, "unknown Java {version}"),
+            version => write!(f, "Java {version}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JavaClass;
+
+    #[test]
+    fn test_java_release() {
+        use JavaRelease::*;
+        let expected = [
+            (45, Java1_1, "Java 1.1"),
+            (46, Java1_2, "Java 1.2"),
+            (47, Java1_3, "Java 1.3"),
+            (48, Java1_4, "Java 1.4"),
+            (49, Java5, "Java 5"),
+            (50, Java6, "Java 6"),
+            (51, Java7, "Java 7"),
+            (52, Java8, "Java 8"),
+            (53, Java9, "Java 9"),
+            (54, Java10, "Java 10"),
+            (55, Java11, "Java 11"),
+            (56, Java12, "Java 12"),
+            (57, Java13, "Java 13"),
+            (58, Java14, "Java 14"),
+            (59, Java15, "Java 15"),
+            (60, Java16, "Java 16"),
+            (61, Java17, "Java 17"),
+            (62, Java18, "Java 18"),
+            (63, Java19, "Java 19"),
+            (64, Java20, "Java 20"),
+            (65, Java21, "Java 21"),
+            (66, Java22, "Java 22"),
+            (67, Java23, "Java 23"),
+            (68, Java24, "Java 24"),
+            (69, Java25, "Java 25"),
+            (70, Unknown(26), "unknown Java 26"),
+        ];
+        for (major, release, name) in expected {
+            let version = JavaVersion::from(JavaClass { major, minor: 0 });
+            assert_eq!(version.release(), release, "major {major}");
+            assert_eq!(release.to_string(), name);
+            assert_eq!(JavaVersion::from(release), version);
+        }
+        assert_eq!(JavaVersion(0).release(), Unknown(0));
+        assert_eq!(KNOWN.len(), usize::from(crate::LATEST_KNOWN_VERSION));
+    }
+}
@@ -0,0 +1,1047 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashSet},
+    io,
+};
+
+use schemars::{JsonSchema, Schema};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::{
+    JavaVersion, STDIN,
+    archive::{is_bundle, is_tar},
+    cli::{Aggregate, SortOrder},
+};
+
+/// Version of the structure of the JSON output. Bump this whenever something changes in a way that could break consumers,
+/// adding fields is fine.
+pub const SCHEMA_VERSION: u16 = 1;
+
+/// The result for one of the files given on the commandline, the way it is in the `files` of `--format json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(extend("additionalProperties" = false))]
+pub struct FileReport {
+    pub path: String,
+    pub version: JavaVersion,
+    /// the file that was actually read, if `path` is (or goes through) a symlink and anything asked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub real_path: Option<String>,
+    /// how many classes still have debug info, if anything asked
+    #[serde(
+        default,
+        rename = "classesWithDebugInfo",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub with_debug_info: Option<usize>,
+    /// names and versions of the classes inside of an archive, if anything asked for them
+    #[serde(skip)]
+    pub classes: Vec<(String, JavaVersion)>,
+    /// how many classes were looked at, unknown for reports that were read back in
+    #[serde(skip)]
+    pub class_count: Option<usize>,
+}
+
+/// A file that couldn't be scanned, for structured output that shouldn't just end at the first broken file
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[schemars(extend("additionalProperties" = false))]
+pub struct FileError {
+    pub path: String,
+    pub error: String,
+}
+
+/// Sorts the processed files for the final report. The sort is stable, so files that compare equal stay in input order.
+pub fn sort_report(report: &mut [FileReport], order: SortOrder, max: Option<u16>) {
+    // all of these are stable, so ties stay in the order of the inputs
+    match order {
+        SortOrder::Path => report.sort_by(|a, b| a.path.cmp(&b.path)),
+        SortOrder::Version => report.sort_by_key(|file| file.version.clone()),
+        SortOrder::VersionDesc => report.sort_by_key(|file| Reverse(file.version.clone())),
+        SortOrder::OverMax => {
+            // clap makes sure that --max is set for this one
+            let max = max.unwrap_or(u16::MAX);
+            // files that aren't over max at all sort as `None`, so reversing puts them last
+            report.sort_by_key(|file| Reverse(file.version.over_max(max)))
+        }
+    }
+}
+
+/// Knobs for [`render_text`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TextOptions {
+    pub max: Option<u16>,
+    /// list the files under their version instead of one line per file
+    pub group_by_version: bool,
+    /// only list this many files per version when grouping, the counts are still the real ones
+    pub limit_per_version: Option<usize>,
+    /// an aligned table with the type of every file
+    pub table: bool,
+}
+
+/// The one file that `--aggregate max` or `min` is about. The first one wins if several have the same version
+pub fn aggregate(report: &[FileReport], aggregate: Aggregate) -> Option<&FileReport> {
+    match aggregate {
+        // max_by_key takes the last of equal ones
+        Aggregate::Max => report.iter().rev().max_by_key(|file| &file.version),
+        Aggregate::Min => report.iter().min_by_key(|file| &file.version),
+        Aggregate::List | Aggregate::Classes => None,
+    }
+}
+
+/// `--aggregate max` or `min` as text, a single line
+pub fn render_aggregate_text(report: &[FileReport], mode: Aggregate, max: Option<u16>) -> String {
+    let Some(file) = aggregate(report, mode) else {
+        return String::new();
+    };
+    let which = if mode == Aggregate::Min {
+        "Lowest"
+    } else {
+        "Highest"
+    };
+    let mut out = format!(
+        "{which} version of {} file(s) is {} ({})",
+        report.len(),
+        file.version,
+        file.path
+    );
+    if let Some(delta) = max.and_then(|max| file.version.over_max(max)) {
+        out.push_str(&format!(", {delta} over the maximum"));
+    }
+    out.push('\n');
+    out
+}
+
+/// `--aggregate max` or `min` as JSON, with the version and the file it comes from at the top instead of a list of files
+pub fn render_aggregate_json(report: &[FileReport], mode: Aggregate) -> Value {
+    let mut fields = header_fields();
+    let name = if mode == Aggregate::Min { "min" } else { "max" };
+    fields.insert("aggregate".to_owned(), json!(name));
+    fields.insert("files".to_owned(), json!(report.len()));
+    if let Some(file) = aggregate(report, mode) {
+        fields.insert("version".to_owned(), json!(file.version));
+        fields.insert("path".to_owned(), json!(file.path));
+    }
+    Value::Object(fields)
+}
+
+/// The files with classes above `max`, with the names of those classes, for every version above it
+type ClassesAbove<'a> = BTreeMap<&'a JavaVersion, BTreeMap<&'a str, Vec<&'a str>>>;
+
+/// For `--aggregate classes`. Class files that were passed in directly are an entry without any classes
+fn classes_above(report: &[FileReport], max: u16) -> ClassesAbove<'_> {
+    let mut versions = ClassesAbove::new();
+    for file in report {
+        if file.classes.is_empty() && *file.version > max {
+            versions
+                .entry(&file.version)
+                .or_default()
+                .entry(&file.path)
+                .or_default();
+        }
+        for (class, version) in &file.classes {
+            if **version > max {
+                let classes = versions
+                    .entry(version)
+                    .or_default()
+                    .entry(&file.path)
+                    .or_default();
+                classes.push(class);
+            }
+        }
+    }
+    for classes in versions.values_mut().flat_map(BTreeMap::values_mut) {
+        classes.sort_unstable();
+    }
+    versions
+}
+
+/// How many different classes there are in `files`, going by their name in their own jar. The same shaded class in
+/// fifty jars is one class
+fn distinct_classes<'a>(files: impl IntoIterator<Item = (&'a &'a str, &'a Vec<&'a str>)>) -> usize {
+    let mut names = HashSet::new();
+    for (path, classes) in files {
+        if classes.is_empty() {
+            names.insert(*path);
+        }
+        names.extend(
+            classes
+                .iter()
+                .map(|class| class.rsplit("!/").next().unwrap_or(class)),
+        );
+    }
+    names.len()
+}
+
+/// `--aggregate classes` as text, with a line for the whole run and the classes of every file by version
+pub fn render_classes_above_text(report: &[FileReport], max: u16) -> String {
+    let versions = classes_above(report, max);
+    let max = JavaVersion(max);
+    if versions.is_empty() {
+        return format!(
+            "Nothing above the maximum of {max} in {} file(s)\n",
+            report.len()
+        );
+    }
+    let files: HashSet<_> = versions.values().flat_map(BTreeMap::keys).collect();
+    let mut out = format!(
+        "{} different class(es) above the maximum of {max} in {} of {} file(s)\n",
+        distinct_classes(versions.values().flatten()),
+        files.len(),
+        report.len()
+    );
+    for (version, files) in &versions {
+        out.push_str(&format!(
+            "{version}: {} different class(es) in {} file(s)\n",
+            distinct_classes(files),
+            files.len()
+        ));
+        for (path, classes) in files {
+            out.push_str(&format!("  {path}\n"));
+            for class in classes {
+                out.push_str(&format!("    {class}\n"));
+            }
+        }
+    }
+    out
+}
+
+/// `--aggregate classes` as JSON, the same as [`render_classes_above_text`]
+pub fn render_classes_above_json(report: &[FileReport], max: u16) -> Value {
+    let versions = classes_above(report, max);
+    let mut fields = header_fields();
+    fields.insert("aggregate".to_owned(), json!("classes"));
+    fields.insert("files".to_owned(), json!(report.len()));
+    fields.insert("max".to_owned(), json!(max));
+    fields.insert(
+        "distinctClasses".to_owned(),
+        json!(distinct_classes(versions.values().flatten())),
+    );
+    let versions: Vec<_> = versions
+        .iter()
+        .map(|(version, files)| {
+            let distinct = distinct_classes(files);
+            let files: Vec<_> = files
+                .iter()
+                .map(|(path, classes)| json!({ "path": path, "classes": classes }))
+                .collect();
+            json!({
+                "version": version,
+                "distinctClasses": distinct,
+                "files": files,
+            })
+        })
+        .collect();
+    fields.insert("versions".to_owned(), json!(versions));
+    Value::Object(fields)
+}
+
+/// One line per file, or the files by version if [`TextOptions::group_by_version`] is set
+pub fn render_text(report: &[FileReport], options: &TextOptions) -> String {
+    if options.table {
+        return render_table(report);
+    }
+    let over_max = |version: &JavaVersion| options.max.and_then(|max| version.over_max(max));
+    let mut out = String::new();
+    if options.group_by_version {
+        let mut groups: BTreeMap<&JavaVersion, Vec<&str>> = BTreeMap::new();
+        for file in report {
+            groups.entry(&file.version).or_default().push(&file.path);
+        }
+        for (version, paths) in groups {
+            match over_max(version) {
+                Some(delta) => out.push_str(&format!(
+                    "{version}: {} file(s), {delta} over the maximum\n",
+                    paths.len()
+                )),
+                None => out.push_str(&format!("{version}: {} file(s)\n", paths.len())),
+            }
+            let limit = options.limit_per_version.unwrap_or(usize::MAX);
+            for path in paths.iter().take(limit) {
+                out.push_str(&format!("  {path}\n"));
+            }
+            if paths.len() > limit {
+                out.push_str(&format!("  ... and {} more\n", paths.len() - limit));
+            }
+        }
+        return out;
+    }
+    for FileReport {
+        path,
+        version,
+        real_path,
+        ..
+    } in report
+    {
+        let path = match real_path {
+            Some(real_path) => format!("{path} -> {real_path}"),
+            None => path.clone(),
+        };
+        match over_max(version) {
+            Some(delta) => out.push_str(&format!("{path}: {version}, {delta} over the maximum\n")),
+            None => out.push_str(&format!("{path}: {version}\n")),
+        }
+    }
+    out
+}
+
+/// What kind of file this is, going by its name
+fn file_type(path: &str) -> &'static str {
+    if path == STDIN {
+        "stdin"
+    } else if path.ends_with(".class") {
+        "class"
+    } else if is_tar(path) {
+        "tar"
+    } else if is_bundle(path) {
+        match &path[path.len() - 3..] {
+            "war" => "war",
+            "aar" => "aar",
+            _ => "ear",
+        }
+    } else if path.ends_with(".zip") {
+        "zip"
+    } else if path.ends_with(".7z") {
+        "7z"
+    } else {
+        "jar"
+    }
+}
+
+/// `--summary`: `FILE`, `TYPE`, the number of `CLASSES` and `VERSION` of every file with the columns lined up. A jar
+/// with fewer classes than expected wasn't read completely, for example because of entries that had to be skipped
+pub fn render_table(report: &[FileReport]) -> String {
+    let mut rows = vec![[
+        "FILE".to_owned(),
+        "TYPE".to_owned(),
+        "CLASSES".to_owned(),
+        "VERSION".to_owned(),
+    ]];
+    for file in report {
+        rows.push([
+            file.path.clone(),
+            file_type(&file.path).to_owned(),
+            file.class_count
+                .map_or_else(|| "-".to_owned(), |count| count.to_string()),
+            file.version.marketing_name(),
+        ]);
+    }
+    let width = |column: usize| {
+        rows.iter()
+            .map(|row| row[column].chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let (path_width, type_width, count_width) = (width(0), width(1), width(2));
+    let mut out = String::new();
+    for [path, kind, count, version] in &rows {
+        out.push_str(&format!(
+            "{path:<path_width$}  {kind:<type_width$}  {count:<count_width$}  {version}\n"
+        ));
+    }
+    out
+}
+
+/// The informational section for `--report-zero-class-jars`, nothing at all if there aren't any
+pub fn render_zero_class_jars(jars: &[String]) -> String {
+    if jars.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from("Jars without any class files:\n");
+    for jar in jars {
+        out.push_str(&format!("  {jar}\n"));
+    }
+    out
+}
+
+/// `zeroClassJars` of the JSON output
+pub fn zero_class_jars_field(jars: &[String]) -> (String, Value) {
+    ("zeroClassJars".to_owned(), json!(jars))
+}
+
+/// A JSON Schema of what `--format json` prints by default, for `--json-schema`
+pub fn json_schema() -> Schema {
+    schemars::schema_for!(JsonReport<'static>)
+}
+
+/// Every structured output starts with these, so consumers can tell what they are dealing with
+pub fn header_fields() -> Map<String, Value> {
+    let mut fields = Map::new();
+    fields.insert("schemaVersion".to_owned(), json!(SCHEMA_VERSION));
+    fields.insert("toolVersion".to_owned(), json!(env!("CARGO_PKG_VERSION")));
+    fields
+}
+
+/// `value` the way every structured output is printed, indented by two spaces
+pub fn pretty(value: &impl Serialize) -> String {
+    // the reports are plain structs and maps with string keys, there is nothing that could fail to serialize
+    serde_json::to_string_pretty(value).expect("reports always serialize")
+}
+
+/// [`pretty`], for a value that is `level` levels deep in the document
+fn nested(value: &impl Serialize, level: usize) -> String {
+    // line breaks inside of strings are escaped, so every one of them is between two tokens
+    pretty(value).replace('\n', &format!("\n{}", "  ".repeat(level)))
+}
+
+/// Writes an object whose last field is an array, one element at a time, so the elements never have to be in memory at
+/// the same time. The result looks exactly like [`pretty`] of the whole thing would.
+pub struct StreamingObject<W: io::Write> {
+    out: W,
+    count: usize,
+}
+
+impl<W: io::Write> StreamingObject<W> {
+    /// Writes everything up to the opening bracket of the array in `key`
+    pub fn new(mut out: W, fields: &Map<String, Value>, key: &str) -> io::Result<Self> {
+        let mut start = String::from("{\n");
+        for (field, value) in fields {
+            write_field(&mut start, field, value);
+            start.push_str(",\n");
+        }
+        start.push_str(&format!("  {}: [", pretty(&key)));
+        out.write_all(start.as_bytes())?;
+        Ok(Self { out, count: 0 })
+    }
+
+    pub fn push(&mut self, item: &impl Serialize) -> io::Result<()> {
+        let separator = if self.count == 0 { "\n    " } else { ",\n    " };
+        self.count += 1;
+        self.out
+            .write_all(format!("{separator}{}", nested(item, 2)).as_bytes())
+    }
+
+    /// Closes the array, adds `trailing` fields after it and closes the object
+    pub fn finish(mut self, trailing: &Map<String, Value>) -> io::Result<W> {
+        let mut end = String::from(if self.count == 0 { "]" } else { "\n  ]" });
+        for (field, value) in trailing {
+            end.push_str(",\n");
+            write_field(&mut end, field, value);
+        }
+        end.push_str("\n}\n");
+        self.out.write_all(end.as_bytes())?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// A field of an object on the first level of indentation
+fn write_field(out: &mut String, key: &str, value: &Value) {
+    out.push_str(&format!("  {}: {}", pretty(&key), nested(value, 1)));
+}
+
+/// Lowest and highest version of a report and how many files there are per version
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+#[schemars(extend("additionalProperties" = false))]
+pub struct Summary {
+    pub min: JavaVersion,
+    pub max: JavaVersion,
+    /// number of files by Java version
+    pub histogram: BTreeMap<u16, usize>,
+}
+
+impl Summary {
+    /// `None` if there is nothing to summarize
+    pub fn of(report: &[FileReport]) -> Option<Self> {
+        let mut histogram = BTreeMap::new();
+        for file in report {
+            *histogram.entry(*file.version).or_default() += 1;
+        }
+        Self::from_histogram(histogram)
+    }
+
+    /// For when the report isn't around as a whole, `histogram` counts files by version
+    pub fn from_histogram(histogram: BTreeMap<u16, usize>) -> Option<Self> {
+        Some(Self {
+            min: JavaVersion(*histogram.first_key_value()?.0),
+            max: JavaVersion(*histogram.last_key_value()?.0),
+            histogram,
+        })
+    }
+}
+
+/// What `--batch-summary-json` writes, `violations` only if there is a maximum
+pub fn render_batch_summary(summary: Option<&Summary>, max: Option<u16>) -> Value {
+    let mut fields = header_fields();
+    let files: usize = summary.map_or(0, |summary| summary.histogram.values().sum());
+    fields.insert("files".to_owned(), json!(files));
+    if let Some(summary) = summary
+        && let Value::Object(summary) = json!(summary)
+    {
+        fields.extend(summary);
+    }
+    if let Some(max) = max {
+        let violations: usize = summary.map_or(0, |summary| {
+            summary
+                .histogram
+                .range(max.saturating_add(1)..)
+                .map(|(_, count)| count)
+                .sum()
+        });
+        fields.insert("violations".to_owned(), json!(violations));
+    }
+    Value::Object(fields)
+}
+
+/// Counts `file` for `--count`, once with its version or every class of it on its own if `per_class` is set. A class
+/// file that was passed in directly is a class either way
+pub fn count_versions(counts: &mut BTreeMap<u16, usize>, file: &FileReport, per_class: bool) {
+    if per_class && !file.classes.is_empty() {
+        for (_, version) in &file.classes {
+            *counts.entry(**version).or_default() += 1;
+        }
+    } else {
+        *counts.entry(*file.version).or_default() += 1;
+    }
+}
+
+/// The one line of `--count`, from the lowest version to the highest
+pub fn render_count(counts: &BTreeMap<u16, usize>, per_class: bool) -> String {
+    let unit = |count| match (per_class, count) {
+        (true, 1) => "class",
+        (true, _) => "classes",
+        (false, 1) => "file",
+        (false, _) => "files",
+    };
+    if counts.is_empty() {
+        return format!("No {}", unit(0));
+    }
+    counts
+        .iter()
+        .map(|(version, &count)| format!("{}: {count} {}", JavaVersion(*version), unit(count)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn render_summary(summary: &Summary) -> String {
+    let mut out = format!("Lowest: {}, highest: {}\n", summary.min, summary.max);
+    for (version, count) in &summary.histogram {
+        out.push_str(&format!("{}: {count} file(s)\n", JavaVersion(*version)));
+    }
+    out
+}
+
+/// Everything `--format json` prints by default
+// the same as `header_fields` and what `StreamingObject` gets, just in one piece. The doc comments end up in the
+// schema, so they are written for whoever reads that
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(
+    title = "java-classfile-version --format json",
+    extend("additionalProperties" = false)
+)]
+pub struct JsonReport<'a> {
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    schema_version: u16,
+    tool_version: &'static str,
+    pub files: Vec<FileEntry<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<&'a Summary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zero_class_jars: Option<&'a [String]>,
+}
+
+/// A file that was scanned, or one that couldn't be
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum FileEntry<'a> {
+    File(&'a FileReport),
+    Error(&'a FileError),
+}
+
+pub fn render_json(report: &[FileReport]) -> JsonReport<'_> {
+    JsonReport {
+        schema_version: SCHEMA_VERSION,
+        tool_version: env!("CARGO_PKG_VERSION"),
+        files: report.iter().map(FileEntry::File).collect(),
+        summary: None,
+        zero_class_jars: None,
+    }
+}
+
+/// Adds the files that couldn't be scanned to the `files` of [`render_json`]
+pub fn push_errors<'a>(json: &mut JsonReport<'a>, errors: &'a [FileError]) {
+    json.files.extend(errors.iter().map(FileEntry::Error));
+}
+
+/// Label values are quoted, so quotes, backslashes and line breaks have to be escaped
+fn prometheus_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Metrics in the text format, for the textfile collector of the node exporter
+pub fn render_prometheus(report: &[FileReport], max: Option<u16>) -> String {
+    let mut out = String::from(concat!(
+        "# HELP classversion_max_java_version Highest Java version of the classes in a file\n",
+        "# TYPE classversion_max_java_version gauge\n"
+    ));
+    for file in report {
+        out.push_str(&format!(
+            "classversion_max_java_version{{file=\"{}\"}} {}\n",
+            prometheus_label(&file.path),
+            *file.version
+        ));
+    }
+    if let Some(max) = max {
+        let violations = report
+            .iter()
+            .filter(|file| file.version.over_max(max).is_some())
+            .count();
+        out.push_str(&format!(
+            concat!(
+                "# HELP classversion_violations_total Number of files above the maximum\n",
+                "# TYPE classversion_violations_total gauge\n",
+                "classversion_violations_total {}\n"
+            ),
+            violations
+        ));
+    }
+    out
+}
+
+/// A level of the package hierarchy, or a class if it has no children
+#[derive(Default)]
+struct TreeNode {
+    children: BTreeMap<String, TreeNode>,
+    version: Option<u16>,
+}
+
+impl TreeNode {
+    fn insert<'a>(&mut self, mut path: impl Iterator<Item = &'a str>, version: u16) {
+        match path.next() {
+            Some(component) => self
+                .children
+                .entry(component.to_owned())
+                .or_default()
+                .insert(path, version),
+            // the same class might show up more than once, e.g. in nested jars with the same name
+            None => self.version = self.version.max(Some(version)),
+        }
+    }
+
+    fn to_json(&self, name: &str) -> Value {
+        if self.children.is_empty() {
+            return json!({ "name": name, "version": self.version });
+        }
+        let children: Vec<_> = self
+            .children
+            .iter()
+            .map(|(name, child)| child.to_json(name))
+            .collect();
+        json!({ "name": name, "children": children })
+    }
+}
+
+/// Nests everything by file, then package and finally class, for feeding into treemap visualizations.
+/// Every node has a `name`, leaves have a `version`, everything else has `children`.
+pub fn render_treemap(report: &[FileReport]) -> Value {
+    let files = report.iter().map(|file| {
+        if file.classes.is_empty() {
+            // a class file, or an archive nobody asked to look into
+            return json!({ "name": file.path, "version": file.version });
+        }
+        let mut root = TreeNode::default();
+        for (name, version) in &file.classes {
+            // nested jars look like `lib/foo.jar!/com/example/Foo.class`, the jar is just another level
+            root.insert(name.split(['/', '!']).filter(|c| !c.is_empty()), **version);
+        }
+        root.to_json(&file.path)
+    });
+
+    let mut fields = header_fields();
+    fields.insert("name".to_owned(), json!(""));
+    fields.insert("children".to_owned(), Value::Array(files.collect()));
+    Value::Object(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::report_of;
+
+    fn files_of(report: &[FileReport]) -> Vec<&str> {
+        report.iter().map(|file| file.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sort_report_by_version() {
+        let mut report = report_of(&[("a.jar", 17), ("b.jar", 8), ("c.jar", 11), ("d.jar", 8)]);
+        sort_report(&mut report, SortOrder::Version, None);
+        assert_eq!(files_of(&report), vec!["b.jar", "d.jar", "c.jar", "a.jar"]);
+        sort_report(&mut report, SortOrder::VersionDesc, None);
+        assert_eq!(files_of(&report), vec!["a.jar", "c.jar", "b.jar", "d.jar"]);
+    }
+
+    #[test]
+    fn test_sort_report_by_path() {
+        let mut report = report_of(&[("lib/b.jar", 8), ("app.jar", 17), ("lib/a.jar", 11)]);
+        sort_report(&mut report, SortOrder::Path, None);
+        assert_eq!(files_of(&report), vec!["app.jar", "lib/a.jar", "lib/b.jar"]);
+    }
+
+    #[test]
+    fn test_sort_report_over_max() {
+        let mut report = report_of(&[
+            ("ok.jar", 8),
+            ("little.jar", 12),
+            ("lots.jar", 21),
+            ("exact.jar", 11),
+            ("also-little.jar", 12),
+        ]);
+        sort_report(&mut report, SortOrder::OverMax, Some(11));
+        assert_eq!(
+            files_of(&report),
+            vec![
+                "lots.jar",
+                "little.jar",
+                "also-little.jar",
+                "ok.jar",
+                "exact.jar"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_text_real_path() {
+        let mut report = report_of(&[("current.jar", 17), ("other.jar", 8)]);
+        report[0].real_path = Some("/opt/app/app-1.4.0.jar".to_owned());
+        assert_eq!(
+            render_text(&report, &TextOptions::default()),
+            "current.jar -> /opt/app/app-1.4.0.jar: Java 17\nother.jar: Java 1.8\n"
+        );
+        let json = serde_json::to_string(&report[0]).unwrap();
+        assert_eq!(
+            json,
+            r#"{"path":"current.jar","version":17,"realPath":"/opt/app/app-1.4.0.jar"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<FileReport>(&json).unwrap(),
+            report[0]
+        );
+    }
+
+    #[test]
+    fn test_render_table() {
+        let mut report = report_of(&[("lib/app.jar", 17), ("Foo.class", 8), ("layer.tar.gz", 11)]);
+        report[0].class_count = Some(1200);
+        report[1].class_count = Some(1);
+        let options = TextOptions {
+            table: true,
+            ..TextOptions::default()
+        };
+        assert_eq!(
+            render_text(&report, &options),
+            "FILE          TYPE   CLASSES  VERSION\n\
+             lib/app.jar   jar    1200     17\n\
+             Foo.class     class  1        1.8\n\
+             layer.tar.gz  tar    -        11\n"
+        );
+    }
+
+    #[test]
+    fn test_render_text_grouped() {
+        let report = report_of(&[("a.jar", 8), ("b.jar", 17), ("c.jar", 8), ("d.jar", 8)]);
+        let options = TextOptions {
+            max: Some(11),
+            group_by_version: true,
+            limit_per_version: Some(2),
+            ..TextOptions::default()
+        };
+        assert_eq!(
+            render_text(&report, &options),
+            "Java 1.8: 3 file(s)\n  a.jar\n  c.jar\n  ... and 1 more\nJava 17: 1 file(s), 6 over the maximum\n  b.jar\n"
+        );
+    }
+
+    #[test]
+    fn test_render_zero_class_jars() {
+        assert_eq!(render_zero_class_jars(&[]), "");
+        assert_eq!(
+            render_zero_class_jars(&["resources.jar".to_owned()]),
+            "Jars without any class files:\n  resources.jar\n"
+        );
+    }
+
+    #[test]
+    fn test_render_prometheus() {
+        let report = report_of(&[("app.jar", 17), ("C:\\weird \"dir\"\n/old.jar", 8)]);
+        assert_eq!(
+            render_prometheus(&report, Some(11)),
+            concat!(
+                "# HELP classversion_max_java_version Highest Java version of the classes in a file\n",
+                "# TYPE classversion_max_java_version gauge\n",
+                "classversion_max_java_version{file=\"app.jar\"} 17\n",
+                "classversion_max_java_version{file=\"C:\\\\weird \\\"dir\\\"\\n/old.jar\"} 8\n",
+                "# HELP classversion_violations_total Number of files above the maximum\n",
+                "# TYPE classversion_violations_total gauge\n",
+                "classversion_violations_total 1\n"
+            )
+        );
+        assert!(!render_prometheus(&report, None).contains("violations"));
+    }
+
+    #[test]
+    fn test_push_errors() {
+        let report = report_of(&[("a.jar", 17)]);
+        let errors = [FileError {
+            path: "b.jar".to_owned(),
+            error: "Not a jar or tar file".to_owned(),
+        }];
+        let mut json = render_json(&report);
+        push_errors(&mut json, &errors);
+        assert_eq!(
+            json!(json.files).to_string(),
+            r#"[{"path":"a.jar","version":17},{"path":"b.jar","error":"Not a jar or tar file"}]"#
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        let report = report_of(&[("a.jar", 17)]);
+        assert_eq!(
+            json!(render_json(&report)).to_string(),
+            format!(
+                r#"{{"schemaVersion":1,"toolVersion":"{}","files":[{{"path":"a.jar","version":17}}]}}"#,
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(Summary::of(&[]), None);
+        let summary =
+            Summary::of(&report_of(&[("a.jar", 17), ("b.jar", 8), ("c.jar", 17)])).unwrap();
+        assert_eq!(
+            json!(summary).to_string(),
+            r#"{"min":8,"max":17,"histogram":{"8":1,"17":2}}"#
+        );
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let report = report_of(&[("a.jar", 11), ("b.jar", 17), ("c.jar", 8), ("d.jar", 17)]);
+        assert_eq!(
+            render_aggregate_text(&report, Aggregate::Max, Some(11)),
+            "Highest version of 4 file(s) is Java 17 (b.jar), 6 over the maximum\n"
+        );
+        assert_eq!(
+            render_aggregate_text(&report, Aggregate::Min, None),
+            "Lowest version of 4 file(s) is Java 1.8 (c.jar)\n"
+        );
+        assert_eq!(render_aggregate_text(&[], Aggregate::Max, None), "");
+        assert_eq!(aggregate(&report, Aggregate::List), None);
+        assert_eq!(
+            render_aggregate_json(&report, Aggregate::Min).to_string(),
+            format!(
+                r#"{{"schemaVersion":1,"toolVersion":"{}","aggregate":"min","files":4,"version":8,"path":"c.jar"}}"#,
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn test_classes_above() {
+        let mut report = report_of(&[("a.jar", 21), ("b.jar", 21), ("c.jar", 8), ("D.class", 17)]);
+        let class = |name: &str, version| (name.to_owned(), JavaVersion(version));
+        report[0].classes = vec![class("com/Shaded.class", 21), class("com/A.class", 11)];
+        report[1].classes = vec![
+            class("lib/inner.jar!/com/Shaded.class", 21),
+            class("com/B.class", 17),
+        ];
+        assert_eq!(
+            render_classes_above_text(&report, 11),
+            "3 different class(es) above the maximum of Java 11 in 3 of 4 file(s)
+Java 17: 2 different class(es) in 2 file(s)
+  D.class
+  b.jar
+    com/B.class
+Java 21: 1 different class(es) in 2 file(s)
+  a.jar
+    com/Shaded.class
+  b.jar
+    lib/inner.jar!/com/Shaded.class
+"
+        );
+        assert_eq!(
+            render_classes_above_text(&report, 21),
+            "Nothing above the maximum of Java 21 in 4 file(s)\n"
+        );
+        assert_eq!(
+            render_classes_above_json(&report, 17).to_string(),
+            format!(
+                r#"{{"schemaVersion":1,"toolVersion":"{}","aggregate":"classes","files":4,"max":17,"distinctClasses":1,"versions":[{{"version":21,"distinctClasses":1,"files":[{{"path":"a.jar","classes":["com/Shaded.class"]}},{{"path":"b.jar","classes":["lib/inner.jar!/com/Shaded.class"]}}]}}]}}"#,
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn test_count() {
+        let mut report = report_of(&[("a.jar", 8), ("b.jar", 17), ("C.class", 8), ("d.jar", 11)]);
+        report[1].classes = vec![
+            ("Foo.class".to_owned(), JavaVersion(17)),
+            ("Bar.class".to_owned(), JavaVersion(8)),
+        ];
+        let count = |per_class| {
+            let mut counts = BTreeMap::new();
+            for file in &report {
+                count_versions(&mut counts, file, per_class);
+            }
+            render_count(&counts, per_class)
+        };
+        assert_eq!(
+            count(false),
+            "Java 1.8: 2 files, Java 11: 1 file, Java 17: 1 file"
+        );
+        assert_eq!(
+            count(true),
+            "Java 1.8: 3 classes, Java 11: 1 class, Java 17: 1 class"
+        );
+        assert_eq!(render_count(&BTreeMap::new(), false), "No files");
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema = json!(json_schema());
+        let properties = |schema: &Value| -> Vec<String> {
+            match schema.get("properties") {
+                Some(Value::Object(fields)) => fields.keys().cloned().collect(),
+                _ => Vec::new(),
+            }
+        };
+        let definition = |name| &schema["$defs"][name];
+        let fields = |json: Value| match json {
+            Value::Object(fields) => fields.into_iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            _ => Vec::new(),
+        };
+        let file = FileReport {
+            real_path: Some("lib/app.jar".to_owned()),
+            with_debug_info: Some(3),
+            ..report_of(&[("app.jar", 17)]).remove(0)
+        };
+        let error = FileError {
+            path: "broken.jar".to_owned(),
+            error: "Not a jar".to_owned(),
+        };
+        let report = [file];
+        let summary = Summary::of(&report).unwrap();
+        let mut output = render_json(&report);
+        output.summary = Some(&summary);
+        output.zero_class_jars = Some(&[]);
+        for (json, schema) in [
+            (json!(output), &schema),
+            (json!(report[0]), definition("FileReport")),
+            (json!(error), definition("FileError")),
+            (json!(summary), definition("Summary")),
+        ] {
+            assert_eq!(fields(json), properties(schema));
+        }
+    }
+
+    #[test]
+    fn test_render_batch_summary() {
+        let report = report_of(&[("a.jar", 17), ("b.jar", 8), ("c.jar", 21)]);
+        let summary = Summary::of(&report);
+        assert_eq!(
+            render_batch_summary(summary.as_ref(), Some(11)).to_string(),
+            format!(
+                r#"{{"schemaVersion":1,"toolVersion":"{}","files":3,"min":8,"max":21,"histogram":{{"8":1,"17":1,"21":1}},"violations":2}}"#,
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+        assert_eq!(
+            render_batch_summary(None, None).to_string(),
+            format!(
+                r#"{{"schemaVersion":1,"toolVersion":"{}","files":0}}"#,
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn test_render_treemap() {
+        let classes = [
+            ("com/example/api/Api.class", 17),
+            ("com/example/api/Model.class", 11),
+            ("com/example/legacy/Old.class", 8),
+            ("org/other/Thing.class", 11),
+        ];
+        let report = vec![
+            FileReport {
+                path: "app.jar".to_owned(),
+                version: JavaVersion(17),
+                classes: classes
+                    .iter()
+                    .map(|(name, version)| (name.to_string(), JavaVersion(*version)))
+                    .collect(),
+                with_debug_info: None,
+                real_path: None,
+                class_count: None,
+            },
+            FileReport {
+                path: "Foo.class".to_owned(),
+                version: JavaVersion(8),
+                classes: Vec::new(),
+                with_debug_info: None,
+                real_path: None,
+                class_count: None,
+            },
+        ];
+
+        let json = render_treemap(&report);
+        let Value::Object(fields) = &json else {
+            panic!("expected an object, got {json}");
+        };
+        let children = &fields["children"];
+        assert_eq!(
+            children.to_string(),
+            concat!(
+                r#"[{"name":"app.jar","children":["#,
+                r#"{"name":"com","children":[{"name":"example","children":["#,
+                r#"{"name":"api","children":[{"name":"Api.class","version":17},{"name":"Model.class","version":11}]},"#,
+                r#"{"name":"legacy","children":[{"name":"Old.class","version":8}]}]}]},"#,
+                r#"{"name":"org","children":[{"name":"other","children":[{"name":"Thing.class","version":11}]}]}]},"#,
+                r#"{"name":"Foo.class","version":8}]"#
+            )
+        );
+        assert!(fields.iter().any(|(key, _)| key == "schemaVersion"));
+    }
+
+    #[test]
+    fn test_streaming_object() {
+        let Value::Object(header) = json!({ "schemaVersion": 1 }) else {
+            unreachable!()
+        };
+        let items: Vec<_> = (0..3).map(|i| json!({ "version": i })).collect();
+        let Value::Object(trailing) = json!({ "more": ["x"] }) else {
+            unreachable!()
+        };
+
+        let mut stream = StreamingObject::new(Vec::new(), &header, "files").unwrap();
+        for item in &items {
+            stream.push(item).unwrap();
+        }
+        let streamed = String::from_utf8(stream.finish(&trailing).unwrap()).unwrap();
+
+        let expected = json!({ "schemaVersion": 1, "files": items, "more": ["x"] });
+        assert_eq!(streamed, pretty(&expected) + "\n");
+        assert_eq!(serde_json::from_str::<Value>(&streamed).unwrap(), expected);
+
+        let empty = StreamingObject::new(Vec::new(), &header, "files").unwrap();
+        let streamed = String::from_utf8(empty.finish(&Map::new()).unwrap()).unwrap();
+        assert_eq!(streamed, "{\n  \"schemaVersion\": 1,\n  \"files\": []\n}\n");
+    }
+
+    #[test]
+    fn test_pretty_nested() {
+        let json = json!({ "files": [{ "version": 8, "path": "a\nb" }], "none": [] });
+        assert_eq!(
+            pretty(&json),
+            "{\n  \"files\": [\n    {\n      \"version\": 8,\n      \"path\": \"a\\nb\"\n    }\n  ],\n  \"none\": []\n}"
+        );
+    }
+}
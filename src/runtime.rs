@@ -0,0 +1,113 @@
+//! Finds out which Java version the installed runtime is, for `--compare-to-runtime`.
+
+use std::{
+    env, fs, io,
+    path::Path,
+    process::{Command, ExitStatus},
+};
+
+use thiserror::Error;
+use tracing::debug;
+
+#[derive(Error, Debug)]
+pub enum RuntimeError {
+    #[error("No java found on the PATH, and JAVA_HOME isn't set either")]
+    NotFound,
+    #[error("Failed to run java -version")]
+    IO(#[from] io::Error),
+    #[error("java -version failed ({status}): {stderr}")]
+    Failed { status: ExitStatus, stderr: String },
+    #[error("Could not make sense of the runtime version in {0:?}")]
+    Unparseable(String),
+}
+
+/// The feature version of a version string, e.g. 17 for `17.0.9`, 8 for `1.8.0_392` and 21 for `21-ea`
+fn feature_version(version: &str) -> Option<u16> {
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    match parts.next()? {
+        // everything up to 8 was 1.x
+        "1" => parts.next()?.parse().ok(),
+        feature => feature.parse().ok(),
+    }
+}
+
+/// `java -version` prints something like `openjdk version "17.0.9" 2023-10-17` to stderr. That's usually the first
+/// line, but the JVM announces `JAVA_TOOL_OPTIONS` and the like before it
+fn parse_version_output(output: &str) -> Option<u16> {
+    let (_, quoted) = output
+        .lines()
+        .find_map(|line| line.split_once("version \""))?;
+    feature_version(quoted.split('"').next()?)
+}
+
+/// `$JAVA_HOME/release` has a `JAVA_VERSION="17.0.9"` line
+fn parse_release(release: &str) -> Option<u16> {
+    release
+        .lines()
+        .find_map(|line| line.strip_prefix("JAVA_VERSION="))
+        .and_then(|version| feature_version(version.trim_matches('"')))
+}
+
+/// Asks the `java` on the PATH for its version, or looks at `$JAVA_HOME/release` if there isn't one
+pub fn runtime_version() -> Result<u16, RuntimeError> {
+    match Command::new("java").arg("-version").output() {
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            debug!("java -version says {stderr}");
+            if !output.status.success() {
+                return Err(RuntimeError::Failed {
+                    status: output.status,
+                    stderr: stderr.trim().to_owned(),
+                });
+            }
+            parse_version_output(&stderr).ok_or_else(|| RuntimeError::Unparseable(stderr.into()))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let java_home = env::var_os("JAVA_HOME").ok_or(RuntimeError::NotFound)?;
+            let release = Path::new(&java_home).join("release");
+            debug!("No java on the PATH, reading {}", release.display());
+            let content = fs::read_to_string(&release).map_err(|_| RuntimeError::NotFound)?;
+            parse_release(&content).ok_or(RuntimeError::Unparseable(content))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version_output() {
+        assert_eq!(
+            parse_version_output(
+                "openjdk version \"17.0.15\" 2025-04-15\nOpenJDK Runtime Environment (build 17.0.15+6)\n"
+            ),
+            Some(17)
+        );
+        assert_eq!(
+            parse_version_output("java version \"1.8.0_392\"\nJava(TM) SE Runtime Environment\n"),
+            Some(8)
+        );
+        assert_eq!(
+            parse_version_output("openjdk version \"23-ea\" 2024-09-17\n"),
+            Some(23)
+        );
+        assert_eq!(
+            parse_version_output(
+                "Picked up JAVA_TOOL_OPTIONS: -Dfile.encoding=\"UTF-8\"\nPicked up _JAVA_OPTIONS: -Xmx1g\nopenjdk version \"21.0.2\" 2024-01-16\n"
+            ),
+            Some(21)
+        );
+        assert_eq!(parse_version_output("command not found"), None);
+    }
+
+    #[test]
+    fn test_parse_release() {
+        assert_eq!(
+            parse_release("IMPLEMENTOR=\"Eclipse Adoptium\"\nJAVA_VERSION=\"21.0.2\"\n"),
+            Some(21)
+        );
+        assert_eq!(parse_release("IMPLEMENTOR=\"Eclipse Adoptium\"\n"), None);
+    }
+}
@@ -0,0 +1,123 @@
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    ops::ControlFlow,
+};
+
+use sevenz_rust::{Password, SevenZReader};
+use tracing::trace;
+
+use crate::{
+    ExtractedJarError,
+    archive::{ArchiveReader, EntryVisitor},
+};
+
+pub const MAGIC_7Z_HEADER: [u8; 6] = [55, 122, 188, 175, 39, 28]; // 377ABCAF271C
+
+/// 7z archive, read with sevenz-rust. Entries are usually compressed together in blocks that can only be decompressed
+/// from the start, so for 7z every entry is decompressed, just not looked at if `visit` doesn't care about it
+pub struct SevenZArchive<R: Read + Seek> {
+    reader: SevenZReader<R>,
+}
+
+impl<R: Read + Seek> SevenZArchive<R> {
+    pub fn new(mut reader: R) -> Result<Self, sevenz_rust::Error> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        reader.rewind()?;
+        Ok(Self {
+            reader: SevenZReader::new(reader, len, Password::empty())?,
+        })
+    }
+}
+
+impl<R: Read + Seek> ArchiveReader for SevenZArchive<R> {
+    fn for_each_entry(&mut self, visit: &mut EntryVisitor) -> Result<(), ExtractedJarError> {
+        // errors of `visit` aren't sevenz-rust ones, they wait here until it's done
+        let mut failed = None;
+        let mut stopped = false;
+        self.reader.for_each_entries(|entry, content| {
+            // returning false only ends the current block, the next one would still come by
+            if stopped || entry.is_directory() {
+                return Ok(!stopped);
+            }
+            trace!(
+                "Found 7z entry {} with {} bytes",
+                entry.name(),
+                entry.size()
+            );
+            match visit(entry.name(), content) {
+                Ok(ControlFlow::Continue(())) => {}
+                Ok(ControlFlow::Break(())) => stopped = true,
+                Err(e) => {
+                    failed = Some(e);
+                    stopped = true;
+                }
+            }
+            if !stopped {
+                // the next entry continues right where this one was left off
+                io::copy(content, &mut io::sink())?;
+            }
+            Ok(!stopped)
+        })?;
+        failed.map_or(Ok(()), Err)
+    }
+
+    /// Like tars, 7z archives are rather a bundle of files than something that is deployed as it is
+    fn scan_nested_jars(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        ExtractedJar, ScanOptions,
+        tests::{classes_of, java_class},
+    };
+    use sevenz_rust::{SevenZArchiveEntry, SevenZWriter};
+    use std::io::Cursor;
+
+    /// Builds a (solid) 7z archive containing the given regular files
+    pub(crate) fn sevenz_with(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = SevenZWriter::new(Cursor::new(Vec::new())).unwrap();
+        for (name, data) in entries {
+            let mut entry = SevenZArchiveEntry::new();
+            entry.name = name.to_string();
+            entry.has_stream = true;
+            writer.push_archive_entry(entry, Some(*data)).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    const CLASS_JAVA_8: [u8; 8] = [202, 254, 186, 190, 0, 0, 0, 52];
+    const CLASS_JAVA_17: [u8; 8] = [202, 254, 186, 190, 0, 0, 0, 61];
+
+    #[test]
+    fn test_7z_with_classes() {
+        let archive = sevenz_with(&[
+            ("Foo.class", &CLASS_JAVA_8),
+            ("README", b"not a class"),
+            ("a/Bar.class", &CLASS_JAVA_17),
+        ]);
+        assert!(archive.starts_with(&MAGIC_7Z_HEADER));
+        let mut archive = SevenZArchive::new(Cursor::new(archive)).unwrap();
+        let extracted = ExtractedJar::from_archive(&mut archive, &ScanOptions::default()).unwrap();
+        assert_eq!(classes_of(&extracted), vec![java_class(52), java_class(61)]);
+    }
+
+    #[test]
+    fn test_7z_nested_jar() {
+        let jar = crate::tests::jar_with(&[("Foo.class", &CLASS_JAVA_17)]);
+        let archive = sevenz_with(&[("lib/foo.jar", &jar)]);
+        let mut archive = SevenZArchive::new(Cursor::new(archive)).unwrap();
+        let extracted = ExtractedJar::from_archive(&mut archive, &ScanOptions::default()).unwrap();
+        assert_eq!(classes_of(&extracted), vec![java_class(61)]);
+    }
+
+    #[test]
+    fn test_7z_broken() {
+        let mut archive = sevenz_with(&[("Foo.class", &CLASS_JAVA_8)]);
+        archive.truncate(20);
+        assert!(SevenZArchive::new(Cursor::new(archive)).is_err());
+    }
+}
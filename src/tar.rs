@@ -0,0 +1,300 @@
+use std::{
+    io::{self, BufRead, BufReader, Read},
+    ops::ControlFlow,
+};
+
+use flate2::read::GzDecoder;
+use thiserror::Error;
+use tracing::{debug, trace};
+
+use crate::{
+    ExtractedJarError,
+    archive::{ArchiveReader, EntryVisitor},
+};
+
+const BLOCK_SIZE: usize = 512;
+pub const MAGIC_GZIP_HEADER: [u8; 2] = [31, 139]; // 1F8B
+
+#[derive(Error, Debug)]
+pub enum TarError {
+    #[error("I/O Error")]
+    IO(#[from] io::Error),
+    #[error("Not a tar archive")]
+    NotATar,
+    #[error("Invalid header for tar entry, maybe the archive is corrupt?")]
+    InvalidHeader,
+}
+
+/// Bare bones tar reader. Only knows enough about the format to find regular files and their names,
+/// including the GNU and pax extensions for long names that Docker happily produces.
+pub struct TarArchive<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> TarArchive<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    /// Calls `f` for every regular file in the archive, in archive order, until it returns [`ControlFlow::Break`].
+    ///
+    /// Whatever `f` doesn't read of the entry is skipped afterwards, so it is fine to only look at the first couple of bytes.
+    pub fn for_each_file<F, E>(&mut self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(&str, &mut dyn Read) -> Result<ControlFlow<()>, E>,
+        E: From<TarError>,
+    {
+        let mut long_name: Option<String> = None;
+        let mut first = true;
+
+        loop {
+            let mut header = [0; BLOCK_SIZE];
+            if let Err(e) = self.reader.read_exact(&mut header) {
+                // an archive without the trailing zero blocks is sloppy, but still readable
+                if e.kind() == io::ErrorKind::UnexpectedEof && !first {
+                    return Ok(());
+                }
+                return Err(TarError::from(e).into());
+            }
+
+            // end of archive is marked by (at least) one block of zeros
+            if header.iter().all(|&b| b == 0) {
+                return Ok(());
+            }
+
+            if !checksum_matches(&header) {
+                return Err(if first {
+                    TarError::NotATar
+                } else {
+                    TarError::InvalidHeader
+                }
+                .into());
+            }
+            first = false;
+
+            let size = parse_size(&header[124..136]).ok_or(TarError::InvalidHeader)?;
+            let padding = (BLOCK_SIZE as u64 - size % BLOCK_SIZE as u64) % BLOCK_SIZE as u64;
+            let mut entry = (&mut self.reader).take(size);
+
+            match header[156] {
+                // GNU long name, the data is the name of the next entry
+                b'L' => {
+                    let mut name = Vec::new();
+                    entry.read_to_end(&mut name).map_err(TarError::from)?;
+                    long_name = Some(cstr(&name));
+                }
+                // pax extended header, might contain a path for the next entry
+                b'x' => {
+                    let mut records = Vec::new();
+                    entry.read_to_end(&mut records).map_err(TarError::from)?;
+                    if let Some(path) = pax_path(&records) {
+                        long_name = Some(path);
+                    }
+                }
+                // regular file
+                b'0' | b'\0' | b'7' => {
+                    let name = long_name.take().unwrap_or_else(|| header_name(&header));
+                    trace!("Found tar entry {name} with {size} bytes");
+                    if f(&name, &mut entry)?.is_break() {
+                        return Ok(());
+                    }
+                }
+                // directories, links, global pax headers and other things that aren't interesting here
+                _ => {
+                    long_name = None;
+                }
+            }
+
+            io::copy(&mut entry, &mut io::sink()).map_err(TarError::from)?;
+            io::copy(&mut (&mut self.reader).take(padding), &mut io::sink())
+                .map_err(TarError::from)?;
+        }
+    }
+}
+
+impl<R: Read> ArchiveReader for TarArchive<R> {
+    fn for_each_entry(&mut self, visit: &mut EntryVisitor) -> Result<(), ExtractedJarError> {
+        self.for_each_file(visit)
+    }
+
+    /// Tars are usually snapshots of a filesystem (like Docker layers), where jars are the things that are deployed
+    fn scan_nested_jars(&self) -> bool {
+        true
+    }
+}
+
+/// Opens a tar archive, transparently decompressing it if it starts with the gzip magic bytes.
+pub fn open_tar<R: Read + 'static>(reader: R) -> Result<TarArchive<Box<dyn Read>>, TarError> {
+    let mut reader = BufReader::new(reader);
+    let is_gzip = reader.fill_buf()?.starts_with(&MAGIC_GZIP_HEADER);
+    let reader: Box<dyn Read> = if is_gzip {
+        debug!("Archive is gzipped, decompressing on the fly");
+        Box::new(GzDecoder::new(reader))
+    } else {
+        Box::new(reader)
+    };
+    Ok(TarArchive::new(reader))
+}
+
+fn checksum_matches(header: &[u8; BLOCK_SIZE]) -> bool {
+    let Some(expected) = parse_octal(&header[148..156]) else {
+        return false;
+    };
+    // the checksum is calculated with the checksum field itself set to spaces
+    let actual: u64 = header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { b' ' } else { b } as u64)
+        .sum();
+    actual == expected
+}
+
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let field = std::str::from_utf8(field).ok()?;
+    let field = field.trim_matches(|c: char| c == '\0' || c == ' ');
+    if field.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(field, 8).ok()
+}
+
+fn parse_size(field: &[u8]) -> Option<u64> {
+    // GNU base-256 encoding for files larger than 8GB. Not that a class file would ever be that big
+    if field[0] & 0x80 != 0 {
+        return Some(
+            field[1..]
+                .iter()
+                .fold(u64::from(field[0] & 0x7f), |acc, &b| {
+                    (acc << 8) | u64::from(b)
+                }),
+        );
+    }
+    parse_octal(field)
+}
+
+fn cstr(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn header_name(header: &[u8; BLOCK_SIZE]) -> String {
+    let name = cstr(&header[..100]);
+    // ustar splits long names into a prefix and the name
+    if &header[257..262] == b"ustar" {
+        let prefix = cstr(&header[345..500]);
+        if !prefix.is_empty() {
+            return format!("{prefix}/{name}");
+        }
+    }
+    name
+}
+
+/// pax records look like `<length> <key>=<value>\n`
+fn pax_path(records: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(records)
+        .lines()
+        .filter_map(|record| record.split_once(' '))
+        .filter_map(|(_, record)| record.split_once('='))
+        .find(|(key, _)| *key == "path")
+        .map(|(_, value)| value.to_owned())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        ExtractedJar, ScanOptions,
+        tests::{classes_of, java_class},
+    };
+    use flate2::{Compression, write::GzEncoder};
+    use std::io::{Cursor, Write};
+
+    /// Builds an uncompressed ustar archive containing the given regular files
+    pub(crate) fn tar_with(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (name, data) in entries {
+            let mut header = [0u8; BLOCK_SIZE];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[100..107].copy_from_slice(b"0000644");
+            header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+            header[156] = b'0';
+            header[257..263].copy_from_slice(b"ustar\0");
+            header[263..265].copy_from_slice(b"00");
+            header[148..156].copy_from_slice(b"        ");
+            let checksum: u64 = header.iter().map(|&b| b as u64).sum();
+            header[148..155].copy_from_slice(format!("{checksum:06o}\0").as_bytes());
+            out.extend_from_slice(&header);
+            out.extend_from_slice(data);
+            out.resize(out.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+        }
+        out.extend_from_slice(&[0; BLOCK_SIZE * 2]);
+        out
+    }
+
+    pub(crate) fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    const CLASS_JAVA_8: [u8; 8] = [202, 254, 186, 190, 0, 0, 0, 52];
+
+    #[test]
+    fn test_tar_gz_with_class() {
+        let tar = gzip(&tar_with(&[
+            ("app/README", b"not a class"),
+            ("app/com/example/Foo.class", &CLASS_JAVA_8),
+        ]));
+        let extracted = ExtractedJar::from_archive(
+            &mut open_tar(Cursor::new(tar)).unwrap(),
+            &ScanOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(classes_of(&extracted), vec![java_class(52)]);
+    }
+
+    #[test]
+    fn test_plain_tar_with_class() {
+        let tar = tar_with(&[("Foo.class", &CLASS_JAVA_8)]);
+        let extracted = ExtractedJar::from_archive(
+            &mut open_tar(Cursor::new(tar)).unwrap(),
+            &ScanOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(classes_of(&extracted), vec![java_class(52)]);
+    }
+
+    #[test]
+    fn test_tar_gz_with_jar() {
+        let jar = crate::tests::jar_with(&[("com/example/Foo.class", &CLASS_JAVA_8)]);
+        let tar = gzip(&tar_with(&[("opt/app/lib/foo.jar", &jar)]));
+        let extracted = ExtractedJar::from_archive(
+            &mut open_tar(Cursor::new(tar)).unwrap(),
+            &ScanOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(classes_of(&extracted), vec![java_class(52)]);
+    }
+
+    #[test]
+    fn test_tar_without_classes() {
+        let tar = gzip(&tar_with(&[("etc/hostname", b"localhost")]));
+        let result = ExtractedJar::from_archive(
+            &mut open_tar(Cursor::new(tar)).unwrap(),
+            &ScanOptions::default(),
+        );
+        assert!(matches!(result, Err(ExtractedJarError::NoClassFiles)));
+    }
+
+    #[test]
+    fn test_not_a_tar() {
+        let result = ExtractedJar::from_archive(
+            &mut open_tar(Cursor::new(vec![1; BLOCK_SIZE])).unwrap(),
+            &ScanOptions::default(),
+        );
+        assert!(matches!(
+            result,
+            Err(ExtractedJarError::Tar(TarError::NotATar))
+        ));
+    }
+}
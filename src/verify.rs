@@ -0,0 +1,112 @@
+//! The `verify` subcommand: an explicit check that artifacts are built for an expected version, as an alternative to `--max`.
+
+use thiserror::Error;
+
+use crate::{JavaVersion, report::FileReport};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Verify {
+    pub expect: u16,
+    /// if set, the version has to be exactly `expect`, lower isn't good enough
+    pub exact: bool,
+}
+
+#[derive(Error, Debug, PartialEq)]
+#[error("{count} file(s) are not {expected}:{list}", count = .mismatches.len(), list = list(.mismatches))]
+pub struct VerifyError {
+    expected: String,
+    mismatches: Vec<(String, JavaVersion)>,
+}
+
+fn list(mismatches: &[(String, JavaVersion)]) -> String {
+    mismatches
+        .iter()
+        .map(|(path, version)| format!("\n  {path}: {version}"))
+        .collect()
+}
+
+impl Verify {
+    pub fn matches(&self, version: &JavaVersion) -> bool {
+        if self.exact {
+            **version == self.expect
+        } else {
+            **version <= self.expect
+        }
+    }
+
+    /// Human readable form of what is expected, e.g. `Java 17 or lower`
+    pub fn expected(&self) -> String {
+        let expect = JavaVersion(self.expect);
+        if self.exact {
+            expect.to_string()
+        } else {
+            format!("{expect} or lower")
+        }
+    }
+
+    pub fn check(&self, report: &[FileReport]) -> Result<(), VerifyError> {
+        let mismatches: Vec<_> = report
+            .iter()
+            .filter(|file| !self.matches(&file.version))
+            .map(|file| (file.path.clone(), file.version.clone()))
+            .collect();
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(VerifyError {
+                expected: self.expected(),
+                mismatches,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::report_of;
+
+    #[test]
+    fn test_verify_pass() {
+        let verify = Verify {
+            expect: 17,
+            exact: false,
+        };
+        assert_eq!(
+            verify.check(&report_of(&[("a.jar", 17), ("b.jar", 11)])),
+            Ok(())
+        );
+        let exact = Verify {
+            expect: 17,
+            exact: true,
+        };
+        assert_eq!(exact.check(&report_of(&[("a.jar", 17)])), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_fail() {
+        let verify = Verify {
+            expect: 17,
+            exact: false,
+        };
+        let error = verify
+            .check(&report_of(&[("a.jar", 17), ("b.jar", 21)]))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "1 file(s) are not Java 17 or lower:\n  b.jar: Java 21"
+        );
+
+        let exact = Verify {
+            expect: 17,
+            exact: true,
+        };
+        let error = exact
+            .check(&report_of(&[("a.jar", 17), ("b.jar", 11)]))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "1 file(s) are not Java 17:\n  b.jar: Java 11"
+        );
+    }
+}
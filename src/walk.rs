@@ -0,0 +1,234 @@
+//! `--recursive`: turns directories passed as inputs into the files inside of them. Also expands glob patterns
+//! in the inputs, for shells that don't (or do it differently).
+
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+use tracing::{debug, trace, warn};
+
+use crate::{
+    archive::is_tar,
+    download::is_url,
+    glob::{Glob, GlobError},
+};
+
+#[derive(Error, Debug)]
+pub enum WalkError {
+    #[error("Failed to read directory {path}")]
+    IO {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("{0} is a directory, pass --recursive to scan everything inside of it")]
+    Directory(String),
+    #[error(transparent)]
+    Glob(#[from] GlobError),
+}
+
+/// Whether a file found in a directory is something that can be scanned
+fn is_scannable(path: &Path) -> bool {
+    let extension = path.extension().and_then(|e| e.to_str());
+    matches!(
+        extension,
+        Some("class" | "jar" | "war" | "ear" | "aar" | "zip" | "7z")
+    ) || path.to_str().is_some_and(is_tar)
+}
+
+/// Replaces directories in `inputs` with every class, jar, tar and 7z archive below them, in a stable order.
+/// Without `recursive`, a directory is an error. Directories that were seen already (because of symlinks) are skipped
+pub fn expand(inputs: Vec<String>, recursive: bool) -> Result<Vec<String>, WalkError> {
+    let mut files = Vec::new();
+    let mut visited = HashSet::new();
+    for input in inputs {
+        let path = Path::new(&input);
+        if !path.is_dir() {
+            files.push(input);
+            continue;
+        }
+        if !recursive {
+            return Err(WalkError::Directory(input));
+        }
+        let before = files.len();
+        walk(path, &mut visited, &mut files)?;
+        debug!("Found {} file(s) in {input}", files.len() - before);
+    }
+    Ok(files)
+}
+
+fn has_wildcards(component: &str) -> bool {
+    component.contains(['*', '?', '['])
+}
+
+/// Replaces the glob patterns in `inputs` with the files they match, sorted like a shell would. Anything that isn't a
+/// pattern (or is, but is also the name of an existing file) is left as it is. Directories are never matched.
+/// A pattern that doesn't match anything is dropped with a warning
+pub fn expand_globs(inputs: Vec<String>) -> Result<Vec<String>, WalkError> {
+    let mut files = Vec::new();
+    for input in inputs {
+        if !has_wildcards(&input) || is_url(&input) || Path::new(&input).exists() {
+            files.push(input);
+            continue;
+        }
+        let pattern = input.replace('\\', "/");
+        let glob = Glob::anchored(pattern.strip_prefix("./").unwrap_or(&pattern))?;
+        // the components before the first wildcard are where to start looking
+        let components: Vec<_> = pattern.split('/').collect();
+        let literal = components.iter().take_while(|c| !has_wildcards(c)).count();
+        let base = match components[..literal].join("/") {
+            base if base.is_empty() && literal > 0 => "/".to_owned(),
+            base => base,
+        };
+        let depth = (!components[literal..].iter().any(|c| c.contains("**")))
+            .then_some(components.len() - literal);
+        let mut matched = Vec::new();
+        let dir = if base.is_empty() { "." } else { &base };
+        if Path::new(dir).is_dir() {
+            walk_glob(&base, depth, &glob, &mut HashSet::new(), &mut matched)?;
+        }
+        if matched.is_empty() {
+            warn!("{input} doesn't match any files");
+        }
+        debug!("{input} matches {} file(s)", matched.len());
+        matched.sort();
+        files.extend(matched);
+    }
+    Ok(files)
+}
+
+/// Collects the files below `dir` that match `glob`, going at most `depth` directories deep. `dir` is empty for the
+/// current directory, so that the paths don't all start with `./`
+fn walk_glob(
+    dir: &str,
+    depth: Option<usize>,
+    glob: &Glob,
+    visited: &mut HashSet<PathBuf>,
+    files: &mut Vec<String>,
+) -> Result<(), WalkError> {
+    let path = if dir.is_empty() {
+        Path::new(".")
+    } else {
+        Path::new(dir)
+    };
+    let io_error = |source| WalkError::IO {
+        path: path.display().to_string(),
+        source,
+    };
+    if depth == Some(0) || !visited.insert(fs::canonicalize(path).map_err(io_error)?) {
+        return Ok(());
+    }
+    for entry in fs::read_dir(path).map_err(io_error)? {
+        let name = entry.map_err(io_error)?.file_name();
+        let name = name.to_string_lossy();
+        let child = match dir {
+            "" => name.into_owned(),
+            dir if dir.ends_with('/') => format!("{dir}{name}"),
+            dir => format!("{dir}/{name}"),
+        };
+        if Path::new(&child).is_dir() {
+            walk_glob(&child, depth.map(|depth| depth - 1), glob, visited, files)?;
+        } else if glob.is_match(&child) {
+            files.push(child);
+        }
+    }
+    Ok(())
+}
+
+fn walk(
+    dir: &Path,
+    visited: &mut HashSet<PathBuf>,
+    files: &mut Vec<String>,
+) -> Result<(), WalkError> {
+    let io_error = |source| WalkError::IO {
+        path: dir.display().to_string(),
+        source,
+    };
+    // symlinks can point back up the tree, which would never end
+    if !visited.insert(fs::canonicalize(dir).map_err(io_error)?) {
+        trace!("Already been in {}, skipping it", dir.display());
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(dir)
+        .map_err(io_error)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(io_error)?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            walk(&entry, visited, files)?;
+        } else if is_scannable(&entry) {
+            files.push(entry.display().to_string());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TempDir;
+
+    #[test]
+    fn test_expand() {
+        let dir = TempDir::new("walk");
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+        fs::write(dir.join("a/Foo.class"), b"").unwrap();
+        fs::write(dir.join("a/b/lib.jar"), b"").unwrap();
+        fs::write(dir.join("a/b/layer.tar.gz"), b"").unwrap();
+        fs::write(dir.join("a/README.md"), b"").unwrap();
+        // a loop back to the top
+        std::os::unix::fs::symlink(&dir, dir.join("a/b/up")).unwrap();
+
+        let root = dir.to_str().unwrap().to_owned();
+        let files = expand(vec![root.clone(), "other.jar".to_owned()], true).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                format!("{root}/a/Foo.class"),
+                format!("{root}/a/b/layer.tar.gz"),
+                format!("{root}/a/b/lib.jar"),
+                "other.jar".to_owned(),
+            ]
+        );
+        assert!(matches!(
+            expand(vec![root], false),
+            Err(WalkError::Directory(_))
+        ));
+    }
+
+    #[test]
+    fn test_expand_globs() {
+        let dir = TempDir::new("globs");
+        fs::create_dir_all(dir.join("target/classes/com")).unwrap();
+        fs::create_dir_all(dir.join("lib.jar.d")).unwrap();
+        fs::write(dir.join("target/classes/com/Foo.class"), b"").unwrap();
+        fs::write(dir.join("target/classes/Bar.class"), b"").unwrap();
+        fs::write(dir.join("target/app.jar"), b"").unwrap();
+        fs::write(dir.join("weird[1].jar"), b"").unwrap();
+
+        let root = dir.to_str().unwrap();
+        let files = expand_globs(vec![
+            format!("{root}/target/**/*.class"),
+            "other.jar".to_owned(),
+            format!("{root}/*/*.jar"),
+            format!("{root}/weird[1].jar"),
+            format!("{root}/nothing/*.jar"),
+        ])
+        .unwrap();
+        assert_eq!(
+            files,
+            vec![
+                format!("{root}/target/classes/Bar.class"),
+                format!("{root}/target/classes/com/Foo.class"),
+                "other.jar".to_owned(),
+                format!("{root}/target/app.jar"),
+                format!("{root}/weird[1].jar"),
+            ]
+        );
+    }
+}
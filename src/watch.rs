@@ -0,0 +1,128 @@
+//! `--watch`, which keeps checking the inputs whenever they change until it's stopped with Ctrl-C.
+//!
+//! Changes are found by looking at the size and modification time of every input twice a second, which is plenty for
+//! files that a build writes every now and then and doesn't need anything platform specific.
+
+use std::{
+    fs, thread,
+    time::{Duration, SystemTime},
+};
+
+use tracing::{debug, info, warn};
+
+use crate::{JavaVersion, ScanOptions, is_local, scan_file};
+
+const INTERVAL: Duration = Duration::from_millis(500);
+
+/// What a change of a file is noticed by, `None` if it doesn't exist (anymore)
+type Stamp = Option<(u64, SystemTime)>;
+
+fn stamp(file: &str) -> Stamp {
+    let metadata = fs::metadata(file).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+/// Remembers how the files looked the last time
+struct Watcher {
+    files: Vec<(String, Stamp)>,
+}
+
+impl Watcher {
+    fn new(files: Vec<String>) -> Self {
+        Self {
+            files: files
+                .into_iter()
+                .map(|file| {
+                    let stamp = stamp(&file);
+                    (file, stamp)
+                })
+                .collect(),
+        }
+    }
+
+    /// The files that changed since the last call, files that were removed included
+    fn changed(&mut self) -> Vec<String> {
+        let mut changed = Vec::new();
+        for (file, last) in &mut self.files {
+            let now = stamp(file);
+            if now != *last {
+                *last = now;
+                changed.push(file.clone());
+            }
+        }
+        changed
+    }
+}
+
+/// One line for stdout on whether `file` passes `--max`
+fn check(file: &str, options: &ScanOptions, max: Option<u16>) -> String {
+    match scan_file(file, options) {
+        Ok(result) if max.is_some_and(|max| *result.version > max) => {
+            format!("FAIL {file}: {} is too high", result.version)
+        }
+        Ok(result) => format!("ok   {file}: {}", result.version),
+        Err(e) => format!("FAIL {file}: {e:#}"),
+    }
+}
+
+/// Checks all files once and then again every time one of them changes. Only ever returns if there is nothing to watch
+pub fn watch(files: Vec<String>, options: &ScanOptions, max: Option<u16>) -> anyhow::Result<()> {
+    let (files, skipped): (Vec<_>, Vec<_>) = files.into_iter().partition(|file| is_local(file));
+    for file in skipped {
+        warn!("Can't watch {file}, it's not a file");
+    }
+    if files.is_empty() {
+        anyhow::bail!("Nothing to watch");
+    }
+    for file in &files {
+        println!("{}", check(file, options, max));
+    }
+    info!(
+        "Watching {} file(s){}, stop with Ctrl-C",
+        files.len(),
+        max.map(|max| format!(" against the maximum of {}", JavaVersion(max)))
+            .unwrap_or_default()
+    );
+    let mut watcher = Watcher::new(files);
+    loop {
+        thread::sleep(INTERVAL);
+        for file in watcher.changed() {
+            debug!("{file} changed");
+            println!("{}", check(&file, options, max));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TempDir;
+
+    #[test]
+    fn test_watcher_changed() {
+        let dir = TempDir::new("watch");
+        let class = dir.join("Foo.class");
+        let java_17 = [202, 254, 186, 190, 0, 0, 0, 61];
+        fs::write(&class, java_17).unwrap();
+        let path = class.to_str().unwrap().to_owned();
+        let options = ScanOptions::default();
+        assert_eq!(
+            check(&path, &options, Some(17)),
+            format!("ok   {path}: Java 17")
+        );
+
+        let mut watcher = Watcher::new(vec![path.clone()]);
+        assert!(watcher.changed().is_empty());
+        fs::write(&class, [202, 254, 186, 190, 0, 0, 0, 65, 0]).unwrap();
+        assert_eq!(watcher.changed(), vec![path.clone()]);
+        assert!(watcher.changed().is_empty());
+        assert_eq!(
+            check(&path, &options, Some(17)),
+            format!("FAIL {path}: Java 21 is too high")
+        );
+
+        fs::remove_file(&class).unwrap();
+        assert_eq!(watcher.changed(), vec![path.clone()]);
+        assert!(check(&path, &options, Some(17)).starts_with("FAIL"));
+    }
+}